@@ -0,0 +1,293 @@
+use jason_rs::{CheckReport, JasonBuilder, JasonError, OutputFormat};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+const USAGE: &str = "\
+usage: jason build <file> [options]
+       jason check [<path>...] [--lua <file>]
+
+check compiles every .jason file under the given files and directories
+(default: the current directory) and reports failures without any output.
+
+options:
+  -o, --output <file>      write the result to <file> instead of stdout
+  -f, --format <format>    json, yaml or toml (default: json)
+  -p, --pretty             pretty print json output
+  -w, --watch              rebuild whenever the file, its imports or lua files change
+  -l, --lua <file>         include a lua file, can be repeated
+  -h, --help               print this message
+  -V, --version            print the version";
+
+// exit code for bad command line usage, compile errors use JasonError::exit_code
+const USAGE_EXIT: u8 = 2;
+
+struct BuildArgs {
+    input: String,
+    output: Option<String>,
+    format: OutputFormat,
+    watch: bool,
+    lua_files: Vec<String>,
+}
+
+struct CheckArgs {
+    paths: Vec<String>,
+    lua_files: Vec<String>,
+}
+
+enum Command {
+    Build(BuildArgs),
+    Check(CheckArgs),
+    Help,
+    Version,
+}
+
+fn parse_format(name: &str) -> Result<OutputFormat, String> {
+    match name {
+        "json" => Ok(OutputFormat::Json),
+        "yaml" | "yml" => Ok(OutputFormat::Yaml),
+        "toml" => Ok(OutputFormat::Toml),
+        _ => Err(format!("unknown format '{}', expected json, yaml or toml", name)),
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter();
+
+    let command = match args.next() {
+        Some(command) => command,
+        None => return Err("missing command".to_string()),
+    };
+
+    match command.as_str() {
+        "-h" | "--help" | "help" => return Ok(Command::Help),
+        "-V" | "--version" => return Ok(Command::Version),
+        "build" | "check" => {}
+        other => return Err(format!("unknown command '{}'", other)),
+    }
+    let checking = command == "check";
+
+    let mut inputs = Vec::new();
+    let mut output = None;
+    let mut format = OutputFormat::Json;
+    let mut pretty = false;
+    let mut watch = false;
+    let mut lua_files = Vec::new();
+
+    while let Some(arg) = args.next() {
+        // options that take a value also accept `--option=value`
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |name: &str| match inline_value.clone() {
+            Some(value) => Ok(value),
+            None => args.next().cloned().ok_or_else(|| format!("{} expects a value", name)),
+        };
+
+        match flag {
+            "-l" | "--lua" => lua_files.push(value(flag)?),
+            "-h" | "--help" => return Ok(Command::Help),
+            "-o" | "--output" if !checking => output = Some(value(flag)?),
+            "-f" | "--format" if !checking => format = parse_format(&value(flag)?)?,
+            "-p" | "--pretty" if !checking => pretty = true,
+            "-w" | "--watch" if !checking => watch = true,
+            _ if flag.starts_with('-') => {
+                return Err(format!("unknown option '{}' for {}", flag, command))
+            }
+            _ => inputs.push(arg.clone()),
+        }
+    }
+
+    if checking {
+        if inputs.is_empty() {
+            inputs.push(".".to_string());
+        }
+        return Ok(Command::Check(CheckArgs { paths: inputs, lua_files }));
+    }
+
+    if inputs.len() > 1 {
+        return Err(format!("unexpected argument '{}'", inputs[1]));
+    }
+    let input = inputs.pop().ok_or_else(|| "build expects a .jason file".to_string())?;
+    if pretty && format == OutputFormat::Json {
+        format = OutputFormat::PrettyJson;
+    }
+    Ok(Command::Build(BuildArgs { input, output, format, watch, lua_files }))
+}
+
+fn builder_with_lua(lua_files: &[String]) -> Result<JasonBuilder, JasonError> {
+    let mut builder = JasonBuilder::new();
+    for lua_file in lua_files {
+        builder = builder.include_lua_file(lua_file)?;
+    }
+    Ok(builder)
+}
+
+fn build(args: &BuildArgs) -> Result<String, JasonError> {
+    let builder = builder_with_lua(&args.lua_files)?;
+
+    match args.format {
+        OutputFormat::Json | OutputFormat::PrettyJson => {
+            let json = builder.jason_to_json(&args.input)?;
+            let rendered = if args.format == OutputFormat::PrettyJson {
+                serde_json::to_string_pretty(&json)
+            } else {
+                serde_json::to_string(&json)
+            };
+            // serializing a serde_json::Value can't fail
+            Ok(rendered.unwrap_or_default())
+        }
+        OutputFormat::Yaml => builder.jason_to_yaml(&args.input),
+        OutputFormat::Toml => builder.jason_to_toml(&args.input),
+    }
+}
+
+fn write_output(args: &BuildArgs, mut rendered: String) -> Result<(), ExitCode> {
+    if !rendered.ends_with('\n') {
+        rendered.push('\n');
+    }
+    match &args.output {
+        Some(path) => {
+            if let Err(err) = std::fs::write(path, rendered) {
+                eprintln!("error: failed to write {}: {}", path, err);
+                return Err(ExitCode::from(3));
+            }
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+fn watch(args: &BuildArgs) -> ExitCode {
+    let builder = match builder_with_lua(&args.lua_files) {
+        Ok(builder) => builder,
+        Err(err) => {
+            eprint!("{}", err);
+            return ExitCode::from(err.exit_code() as u8);
+        }
+    };
+    let mut watcher = builder.watch(&args.input, args.format);
+
+    loop {
+        match watcher.poll() {
+            Some(Ok(rendered)) => {
+                if let Err(code) = write_output(args, rendered) {
+                    return code;
+                }
+                eprintln!("[watching {} files]", watcher.watched_files().len());
+            }
+            Some(Err(err)) => {
+                eprint!("{}", err);
+                eprintln!("[watching {} files]", watcher.watched_files().len());
+            }
+            None => {}
+        }
+        std::thread::sleep(Duration::from_millis(300));
+    }
+}
+
+// collects .jason files below `path`, skipping hidden directories and cargo's target
+fn collect_jason_files(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for entry in entries {
+        let name = entry.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if entry.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                collect_jason_files(&entry, files)?;
+            }
+        } else if entry.extension().is_some_and(|ext| ext == "jason") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+fn check(args: &CheckArgs) -> ExitCode {
+    let mut files = Vec::new();
+    for path in &args.paths {
+        if let Err(err) = collect_jason_files(Path::new(path), &mut files) {
+            eprintln!("error: failed to read {}: {}", path, err);
+            return ExitCode::from(3);
+        }
+    }
+
+    let mut failed = 0;
+    for file in &files {
+        let file = file.to_string_lossy();
+        let report = match builder_with_lua(&args.lua_files) {
+            Ok(builder) => builder.jason_check(&file),
+            Err(err) => CheckReport { file: file.to_string(), errors: vec![err] },
+        };
+
+        if report.passed() {
+            println!("ok    {}", report.file);
+            continue;
+        }
+
+        failed += 1;
+        let count = report.errors.len();
+        println!("FAIL  {} ({} error{})", report.file, count, if count == 1 { "" } else { "s" });
+        for err in &report.errors {
+            eprint!("{}", err);
+        }
+    }
+
+    println!("\n{} passed, {} failed", files.len() - failed, failed);
+    if failed > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let command = match parse_args(&args) {
+        Ok(command) => command,
+        Err(msg) => {
+            eprintln!("error: {}\n\n{}", msg, USAGE);
+            return ExitCode::from(USAGE_EXIT);
+        }
+    };
+
+    let build_args = match command {
+        Command::Help => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Command::Version => {
+            println!("jason {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Command::Check(check_args) => return check(&check_args),
+        Command::Build(build_args) => build_args,
+    };
+
+    if build_args.watch {
+        return watch(&build_args);
+    }
+
+    let rendered = match build(&build_args) {
+        Ok(rendered) => rendered,
+        Err(err) => {
+            eprint!("{}", err);
+            return ExitCode::from(err.exit_code() as u8);
+        }
+    };
+
+    match write_output(&build_args, rendered) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(code) => code,
+    }
+}
@@ -0,0 +1,329 @@
+use crate::context::Context;
+use crate::jason_errors::JasonErrorKind;
+use crate::jason_errors::JasonResult;
+use crate::template::Template;
+use crate::token;
+use serde_json::{Map, Number, Value};
+
+/// Functions built into the language, called like templates but implemented natively.
+pub const BUILTINS: &[&str] = &[
+    "len", "keys", "values", "entries", "from_entries", "flatten", "zip",
+    "range", "sum", "min", "max", "avg", "contains", "index_of",
+    "upper", "lower", "split", "join", "replace", "trim", "starts_with", "ends_with", "pad_left", "substr",
+    "matches", "captures", "regex_replace",
+];
+
+// the string builtins, evaluated by `Context::eval_string_builtin`
+const STRING_BUILTINS: &[&str] = &[
+    "upper", "lower", "split", "join", "replace", "trim", "starts_with", "ends_with", "pad_left", "substr",
+];
+
+// the most items `range` will make, anything bigger is almost certainly a mistake
+const MAX_RANGE: usize = 1_000_000;
+
+pub fn is_builtin(name: &str) -> bool {
+    BUILTINS.contains(&name)
+}
+
+// what a builtin accepts for one of its arguments
+#[derive(Debug, Clone, Copy)]
+enum Param {
+    Any,
+    Int,
+    String,
+    StringList,
+    List,
+    NumberList,
+    Object,
+    ListOrString,
+    Collection,
+}
+
+impl Param {
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            Param::Any => true,
+            Param::Int => value.is_i64(),
+            Param::String => value.is_string(),
+            Param::StringList => value.as_array().is_some_and(|items| items.iter().all(Value::is_string)),
+            Param::List => value.is_array(),
+            Param::NumberList => value.as_array().is_some_and(|items| items.iter().all(Value::is_number)),
+            Param::Object => value.is_object(),
+            Param::ListOrString => value.is_array() || value.is_string(),
+            Param::Collection => value.is_array() || value.is_string() || value.is_object(),
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Param::Any => "Any",
+            Param::Int => "Int",
+            Param::String => "String",
+            Param::StringList => "[String]",
+            Param::List => "List",
+            Param::NumberList => "[Number]",
+            Param::Object => "Object",
+            Param::ListOrString => "List or String",
+            Param::Collection => "List, String or Object",
+        }
+    }
+}
+
+// the parameters of a builtin, the ones past `required` are optional, and `repeat` accepts any number of them
+struct Signature {
+    params: &'static [Param],
+    required: usize,
+    repeat: bool,
+}
+
+fn signature(name: &str) -> Signature {
+    let (params, required, repeat): (&'static [Param], usize, bool) = match name {
+        "len" => (&[Param::Collection], 1, false),
+        "keys" | "values" | "entries" => (&[Param::Object], 1, false),
+        "from_entries" => (&[Param::List], 1, false),
+        "flatten" => (&[Param::List, Param::Int], 1, false),
+        "zip" => (&[Param::List, Param::List], 2, true),
+        "range" => (&[Param::Int, Param::Int, Param::Int], 1, false),
+        "sum" | "avg" => (&[Param::NumberList], 1, false),
+        "min" | "max" => (&[Param::List], 1, false),
+        "contains" => (&[Param::Collection, Param::Any], 2, false),
+        "index_of" => (&[Param::ListOrString, Param::Any], 2, false),
+        "upper" | "lower" | "trim" => (&[Param::String], 1, false),
+        "split" | "starts_with" | "ends_with" => (&[Param::String, Param::String], 2, false),
+        "join" => (&[Param::StringList, Param::String], 2, false),
+        "replace" => (&[Param::String, Param::String, Param::String], 3, false),
+        "pad_left" => (&[Param::String, Param::Int, Param::String], 2, false),
+        "substr" => (&[Param::String, Param::Int, Param::Int], 2, false),
+        "matches" | "captures" => (&[Param::String, Param::String], 2, false),
+        "regex_replace" => (&[Param::String, Param::String, Param::String], 3, false),
+        _ => (&[], 0, false),
+    };
+    Signature { params, required, repeat }
+}
+
+fn ordinal(i: usize) -> String {
+    let suffix = match (i % 10, i % 100) {
+        (1, n) if n != 11 => "st",
+        (2, n) if n != 12 => "nd",
+        (3, n) if n != 13 => "rd",
+        _ => "th",
+    };
+    format!("{}{}", i, suffix)
+}
+
+/// Evaluates the arguments, checks them against the builtin's signature and calls it.
+pub fn call(context: &mut Context, name: &str, arguments: &token::Args) -> JasonResult<Option<Value>> {
+    let signature = signature(name);
+    let given = arguments.len();
+    let total = signature.params.len();
+    if given < signature.required || (!signature.repeat && given > total) {
+        let takes = if signature.repeat {
+            format!("{} or more arguments", signature.required)
+        } else if signature.required == total {
+            format!("{} argument{}", total, if total == 1 { "" } else { "s" })
+        } else {
+            format!("{} to {} arguments", signature.required, total)
+        };
+        return Err(context.err(
+            JasonErrorKind::ArityError(name.to_string()),
+            format!("{} takes {} but {} {} given", name, takes, given, if given == 1 { "was" } else { "were" })
+        ));
+    }
+
+    let mut args: Vec<Value> = Vec::with_capacity(given);
+    for (i, node) in arguments.iter().enumerate() {
+        let value = context.to_json(node)?.ok_or_else(||
+            context.err(JasonErrorKind::ValueError, format!("argument {} passed to {} is None", node.plain_sum, name)))?;
+        if let Some(template) = Template::referenced(&value) {
+            return Err(context.template_value_err(template, &format!("passed to {}", name)));
+        }
+        let param = signature.params.get(i).or(signature.params.last()).copied().unwrap_or(Param::Any);
+        if !param.matches(&value) {
+            let found = context.infer_type_from(&value)?;
+            return Err(context.err(
+                JasonErrorKind::TypeError(name.to_string()),
+                format!("{} expects {} for its {} argument found {}", name, param.describe(), ordinal(i + 1), found)
+            ));
+        }
+        args.push(value);
+    }
+
+    let result = match name {
+        "len" => len(&args[0]),
+        "keys" => Value::Array(object(&args[0]).keys().map(|key| Value::String(key.clone())).collect()),
+        "values" => Value::Array(object(&args[0]).values().cloned().collect()),
+        "entries" => Value::Array(
+            object(&args[0]).iter().map(|(key, value)| Value::Array(vec![Value::String(key.clone()), value.clone()])).collect()
+        ),
+        "from_entries" => from_entries(context, &args[0])?,
+        "flatten" => {
+            let depth = args.get(1).and_then(Value::as_i64).unwrap_or(1);
+            Value::Array(flatten(list(&args[0]), depth))
+        },
+        "zip" => zip(&args),
+        "range" => range(context, &args)?,
+        "sum" => sum(context, list(&args[0]))?,
+        "avg" => avg(context, list(&args[0]))?,
+        "min" | "max" => extreme(context, name, list(&args[0]))?,
+        "contains" => Value::Bool(contains(&args[0], &args[1])),
+        "index_of" => Value::Number(index_of(&args[0], &args[1]).into()),
+        _ if STRING_BUILTINS.contains(&name) => context.eval_string_builtin(name, &args)?,
+        "matches" | "captures" | "regex_replace" => context.eval_regex_builtin(name, &args)?,
+        _ => return Err(context.err(
+            JasonErrorKind::UndefinedTemplate(name.to_string()),
+            format!("{} is not a builtin", name)
+        )),
+    };
+    Ok(Some(result))
+}
+
+// only called after the argument was checked against the signature
+fn list(value: &Value) -> &[Value] {
+    value.as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+fn object(value: &Value) -> Map<String, Value> {
+    value.as_object().cloned().unwrap_or_default()
+}
+
+fn len(value: &Value) -> Value {
+    let len = match value {
+        Value::Array(items) => items.len(),
+        Value::String(s) => s.chars().count(),
+        Value::Object(map) => map.len(),
+        _ => 0,
+    };
+    Value::Number(len.into())
+}
+
+fn from_entries(context: &mut Context, value: &Value) -> JasonResult<Value> {
+    let mut map = Map::new();
+    for entry in list(value) {
+        match entry.as_array().map(Vec::as_slice) {
+            Some([Value::String(key), value]) => {
+                map.insert(key.clone(), value.clone());
+            },
+            _ => {
+                let found = context.infer_type_from(entry)?;
+                return Err(context.err(
+                    JasonErrorKind::TypeError("from_entries".to_string()),
+                    format!("from_entries expects [key, value] pairs with String keys found {}", found)
+                ));
+            }
+        }
+    }
+    Ok(Value::Object(map))
+}
+
+fn flatten(items: &[Value], depth: i64) -> Vec<Value> {
+    let mut flat = Vec::new();
+    for item in items {
+        match item {
+            Value::Array(inner) if depth > 0 => flat.extend(flatten(inner, depth - 1)),
+            _ => flat.push(item.clone()),
+        }
+    }
+    flat
+}
+
+// stops at the end of the shortest list
+fn zip(lists: &[Value]) -> Value {
+    let shortest = lists.iter().map(|l| list(l).len()).min().unwrap_or(0);
+    Value::Array(
+        (0..shortest)
+            .map(|i| Value::Array(lists.iter().map(|l| list(l)[i].clone()).collect()))
+            .collect()
+    )
+}
+
+// `range(end)`, `range(start, end)` or `range(start, end, step)`, end is never included
+fn range(context: &Context, args: &[Value]) -> JasonResult<Value> {
+    let ints: Vec<i64> = args.iter().filter_map(Value::as_i64).collect();
+    let (start, end, step) = match ints.as_slice() {
+        [end] => (0, *end, 1),
+        [start, end] => (*start, *end, 1),
+        [start, end, step] => (*start, *end, *step),
+        _ => (0, 0, 1),
+    };
+    if step == 0 {
+        return Err(context.err(JasonErrorKind::ValueError, "range step can't be 0".to_string()));
+    }
+
+    let count = if (step > 0 && start < end) || (step < 0 && start > end) {
+        ((end as i128 - start as i128).abs() + step.unsigned_abs() as i128 - 1) / step.unsigned_abs() as i128
+    } else {
+        0
+    };
+    if count > MAX_RANGE as i128 {
+        return Err(context.err(
+            JasonErrorKind::ValueError,
+            format!("range would make {} items, the most it can make is {}", count, MAX_RANGE)
+        ));
+    }
+    Ok(Value::Array((0..count as i64).map(|i| Value::Number((start + i * step).into())).collect()))
+}
+
+fn float(context: &Context, n: f64) -> JasonResult<Value> {
+    Number::from_f64(n).map(Value::Number).ok_or_else(||
+        context.err(JasonErrorKind::InvalidOperation(n.to_string()), "invalid floating-point number".to_string()))
+}
+
+// stays an Int while every item is one and the total fits
+fn sum(context: &Context, items: &[Value]) -> JasonResult<Value> {
+    let ints: Option<i64> = items.iter().try_fold(0i64, |total, item| total.checked_add(item.as_i64()?));
+    match ints {
+        Some(total) => Ok(Value::Number(total.into())),
+        None => float(context, items.iter().filter_map(Value::as_f64).sum()),
+    }
+}
+
+fn avg(context: &Context, items: &[Value]) -> JasonResult<Value> {
+    if items.is_empty() {
+        return Err(context.err(JasonErrorKind::ValueError, "avg of an empty list".to_string()));
+    }
+    let total: f64 = items.iter().filter_map(Value::as_f64).sum();
+    float(context, total / items.len() as f64)
+}
+
+fn extreme(context: &mut Context, name: &str, items: &[Value]) -> JasonResult<Value> {
+    let Some(mut best) = items.first() else {
+        return Err(context.err(JasonErrorKind::ValueError, format!("{} of an empty list", name)));
+    };
+    for item in &items[1..] {
+        let ordering = match Context::compare_values(item, best) {
+            Some(ordering) => ordering,
+            None => {
+                let item_type = context.infer_type_from(item)?;
+                let best_type = context.infer_type_from(best)?;
+                return Err(context.err(
+                    JasonErrorKind::TypeError(name.to_string()),
+                    format!("{} can't compare {} and {}, only values of the same kind can be compared", name, item_type, best_type)
+                ));
+            }
+        };
+        if (name == "min" && ordering.is_lt()) || (name == "max" && ordering.is_gt()) {
+            best = item;
+        }
+    }
+    Ok(best.clone())
+}
+
+// lists look for an equal item, strings for a substring and objects for a key
+fn contains(collection: &Value, value: &Value) -> bool {
+    match (collection, value) {
+        (Value::Array(items), value) => items.iter().any(|item| Context::values_equal(item, value)),
+        (Value::String(s), Value::String(sub)) => s.contains(sub.as_str()),
+        (Value::Object(map), Value::String(key)) => map.contains_key(key),
+        _ => false,
+    }
+}
+
+// -1 when it isn't there, strings give the index of the first character
+fn index_of(collection: &Value, value: &Value) -> i64 {
+    let index = match (collection, value) {
+        (Value::Array(items), value) => items.iter().position(|item| Context::values_equal(item, value)),
+        (Value::String(s), Value::String(sub)) => s.find(sub.as_str()).map(|byte| s[..byte].chars().count()),
+        _ => None,
+    };
+    index.map_or(-1, |i| i as i64)
+}
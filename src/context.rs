@@ -1,13 +1,13 @@
 use crate::{
-    astnode::ASTNode, jason_errors::{JasonError, JasonResult}, jason_types::JasonType, lua_instance::LuaInstance, template::Template, token::TokenType
+    astnode::ASTNode, builtins, function::Function, jason_errors::{JasonError, JasonResult}, jason_types::JasonType, lua_instance::LuaInstance, template::Template, token::{self, TokenType}
 };
 
 use colored::*;
 
-use crate::jason_to_json;
 use std::{collections::{HashMap, HashSet}, path::Path};
 use mlua::Table;
 use rand::Rng;
+use regex::Regex;
 use serde_json::{Map, Number, Value};
 use std::rc::Rc;
 use std::cell::RefCell;
@@ -19,26 +19,51 @@ use crate::jason_errors::JasonErrorKind;
 #[derive(Debug)]
 pub enum ExportType {
     Template(String, Template),
+    Function(String, Function),
     Variable(String, serde_json::Value),
     TemplateType(String, (Vec<JasonType>, JasonType)),
     VariableType(String, JasonType),
     Type(String, JasonType)
 }
 
+/// Variables bound by a template, function or `map` while it's evaluated, kept apart from the top level ones.
+#[derive(Debug, Default)]
+pub struct Scope {
+    variables: HashMap<String, serde_json::Value>,
+    // lookups stop at a closed scope instead of reaching into the caller's scopes
+    closed: bool,
+}
+
+impl Scope {
+    pub fn open() -> Self {
+        Scope::default()
+    }
+
+    pub fn closed() -> Self {
+        Scope { closed: true, ..Scope::default() }
+    }
+}
+
 #[derive(Debug)]
 pub struct Context {
     pub variables: HashMap<String, serde_json::Value>,
     pub templates: HashMap<String, Template>,
+    pub functions: HashMap<String, Function>,
+    // innermost last, empty at the top level
+    pub scopes: Vec<Scope>,
     pub types: HashMap<String, JasonType>,
     pub variable_types: HashMap<String, JasonType>,
     pub template_types: HashMap<String, (Vec<JasonType>, JasonType)>,
     pub out: serde_json::Value,
+    pub out_node: Option<Rc<ASTNode>>,
     pub source_path: Rc<String>,
     pub lua_instance: Rc<RefCell<LuaInstance>>,
     pub lua_env: Table,
     pub lua_fn_cache: HashMap<String, mlua::RegistryKey>, // cache lua functions
     pub local_root:Option<Rc<ASTNode>>,
     pub imported_from: Rc<RefCell<HashSet<String>>>,
+    pub dependencies: Rc<RefCell<HashSet<String>>>, // every file reached through import or include
+    pub regex_cache: HashMap<String, Regex>, // compiled patterns of the regex builtins
 }
 
 impl Context {    
@@ -59,16 +84,21 @@ impl Context {
         Ok(Context {
             variables: HashMap::new(),
             templates: HashMap::new(),
+            functions: HashMap::new(),
+            scopes: Vec::new(),
             types: HashMap::new(),
             variable_types: HashMap::new(),
             template_types: HashMap::new(),
             out: Value::Null,
+            out_node: None,
             source_path: path,
             lua_instance,
             lua_env,
             lua_fn_cache: HashMap::new(),
             local_root: None,
-            imported_from: RefCell::new(HashSet::new()).into()
+            imported_from: RefCell::new(HashSet::new()).into(),
+            dependencies: RefCell::new(HashSet::new()).into(),
+            regex_cache: HashMap::new(),
         })
     }
     
@@ -543,6 +573,8 @@ impl Context {
                             format!("type mismatches\n expected {}, found {}\n{}", typed_var, infered_type,
                                 if let (JasonType::Object(o1), JasonType::Object(o2)) = (typed_var, &infered_type) {
                                     JasonType::diff_objects(&o1, &o2)                                 
+                                } else if right_value.is_null() && Self::is_nullable(right) {
+                                    format!(" hint: {} was missing, give it a default with {} ?? value", right.plain_sum, right.plain_sum)
                                 } else {"".to_string()}
                             )
                         )
@@ -576,7 +608,7 @@ impl Context {
                     format!("cannot reassign type of {}, existing type is, {}", var_name, self.variable_types.get(&var_name).unwrap())
                 ))
             }
-            let infered_type = self.infer_type_from(&right_value)?;
+            let infered_type = self.infer_type_from_node(right, &right_value)?;
             self.variable_types.insert(var_name.clone(), infered_type);
             self.variables.insert(var_name, right_value);             
         }
@@ -638,8 +670,10 @@ impl Context {
                         ));
                     } 
 
+                    self.dependencies.borrow_mut().insert(import_path.clone());
+
                     // Pass it to the child context
-                    let context = jason_hidden::jason_context_from_file_with_imports(import_path.clone(), self.lua_instance.clone(), self.imported_from.clone())?;
+                    let context = jason_hidden::jason_context_from_file_with_imports(import_path.clone(), self.lua_instance.clone(), self.imported_from.clone(), self.dependencies.clone())?;
 
                     /*let mut context = match jason_hidden::jason_context_from_file(import_path.clone(), self.lua_instance.clone()) {
                         Ok(v) => Ok(v),
@@ -694,6 +728,9 @@ impl Context {
                         .ok_or_else(|| JasonError::new(JasonErrorKind::ValueError, self.source_path.clone(), None, "Argument is Empty"))
                 })
                 .collect::<JasonResult<Vec<Value>>>()?;
+            if let Some(name) = json_values.iter().find_map(Template::find_reference) {
+                return Err(self.template_value_err(name, "passed to lua"));
+            }
             // Now borrow lua
             let lua = self.lua_instance.borrow();
             // Convert to Lua values
@@ -774,6 +811,9 @@ impl Context {
         let right = self.to_json(node.right.as_ref().ok_or_else(||
             JasonError::new(JasonErrorKind::MissingValue, self.source_path.clone(),self.local_root.clone(), "right node missing"))?)?.ok_or_else(||
             JasonError::new(JasonErrorKind::ValueError,self.source_path.clone(), self.local_root.clone(), "right value is None"))?;
+        if let Some(name) = Template::referenced(&left).or(Template::referenced(&right)) {
+            return Err(self.template_value_err(name, "merged with &"));
+        }
         
         match (&left, &right) {
             (Value::Object(_), Value::Object(_)) => {
@@ -792,7 +832,9 @@ impl Context {
         let right = self.to_json(node.right.as_ref().ok_or_else(||
             JasonError::new(JasonErrorKind::MissingValue, self.source_path.clone(),self.local_root.clone(), "right node missing"))?)?.ok_or_else(||
             JasonError::new(JasonErrorKind::ValueError,self.source_path.clone(), self.local_root.clone(), "right value is None"))?;
-       
+        if let Some(name) = Template::referenced(&left).or(Template::referenced(&right)) {
+            return Err(self.template_value_err(name, "added with +"));
+        }
 
 
         match (left, right) {
@@ -850,6 +892,9 @@ impl Context {
         let right = self.to_json(node.right.as_ref().ok_or_else(||
             JasonError::new(JasonErrorKind::MissingValue, self.source_path.clone(),self.local_root.clone(), "right node missing"))?)?.ok_or_else(||
             JasonError::new(JasonErrorKind::ValueError,self.source_path.clone(), self.local_root.clone(), "right value is None"))?;
+        if let Some(name) = Template::referenced(&left) {
+            return Err(self.template_value_err(name, "indexed with at"));
+        }
 
         match (left, right) {
             // [a, b, c, ...] at 0 -> a
@@ -1100,13 +1145,24 @@ impl Context {
         };
         //let mut flat_args: Vec<Token> = args.iter().flat_map(|v| v.iter().cloned()).collect();
 
+        // `list map Template` applies the template to every item
         if args.is_empty() {
-            return Err(JasonError::new(
-                JasonErrorKind::ValueError,
-                self.source_path.clone(),
-                self.local_root.clone(),
-                "map must have at least one argument",
-            ));
+            let applied = self.to_json(right)?.ok_or_else(||
+                self.err(JasonErrorKind::ValueError, format!("{} is None", right.plain_sum)))?;
+            let Some(name) = Template::referenced(&applied) else {
+                let found = self.infer_type_from(&applied)?;
+                return Err(self.err(
+                    JasonErrorKind::TypeError(right.plain_sum.clone()),
+                    format!("map without parameters applies a template to each item, but {} is {}", right.plain_sum, found)
+                ));
+            };
+            let template = self.find_template(name)?;
+            let mut results: Vec<Value> = Vec::with_capacity(values.len());
+            for value in values {
+                results.push(template.apply(self, vec![value])?.ok_or_else(||
+                    self.err(JasonErrorKind::ValueError, format!("template {} resolved to None", name)))?);
+            }
+            return Ok(Some(Value::Array(results)));
         }
 
         let argument = args.remove(0).token.plain(); // first token
@@ -1117,37 +1173,243 @@ impl Context {
         };
         let has_index_argument = !index_argument.is_empty();
         
-        let mut results:Vec<Value> = Vec::with_capacity(values.len());
+        // the bindings live in a scope of their own, so they can't clobber or outlive variables around the map
+        let results = self.with_scope(Scope::open(), |context| {
+            let mut results:Vec<Value> = Vec::with_capacity(values.len());
+            for (i, value) in values.into_iter().enumerate() {
+                context.bind(argument.clone(), value);
+                if has_index_argument {
+                    context.bind(index_argument.clone(), Value::Number(i.into()));
+                }
 
-        for (i, value) in values.into_iter().enumerate() {
-            self.variables.insert(argument.clone(), value);
-            
-            if has_index_argument {
-                self.variables.insert(index_argument.clone(), Value::Number(i.into()));
+                results.push(
+                    context.to_json(right)?
+                        .ok_or_else(|| 
+                            JasonError::new(
+                                JasonErrorKind::ValueError,
+                                context.source_path.clone(),
+                                context.local_root.clone(),
+                                format!("map over {} resulted in None", argument),
+                            )
+                        )?
+                );
+            }
+            Ok(results)
+        })?;
+        
+        Ok(Some(Value::Array(results)))
+    }
+    
+    // the list on the left of `filter` and `reduce`, and the names they bind
+    fn eval_list_operator(&mut self, node: &ASTNode, args: &token::Args, usage: &str) -> JasonResult<(Vec<Value>, Vec<String>)> {
+        let operator = node.token.plain();
+        let mut names: Vec<String> = Vec::new();
+        for arg in args {
+            if arg.token.token_type != TokenType::ID {
+                return Err(self.err(
+                    JasonErrorKind::SyntaxErrorHere(arg.plain_sum.clone()),
+                    format!("{} binds names, {} isn't one. usage: {}", operator, arg.plain_sum, usage)
+                ));
             }
+            names.push(arg.token.plain());
+        }
 
-            results.push(
-                self.to_json(right)?
-                    .ok_or_else(|| 
-                        JasonError::new(
-                            JasonErrorKind::ValueError,
-                            self.source_path.clone(),
-                            self.local_root.clone(),
-                            format!("variable {} in mapping already exists", argument),
-                        )
-                    )?
-            );
+        let values = self.eval_list_operand(node)?;
+        Ok((values, names))
+    }
 
-            if has_index_argument {
-                self.variables.remove(&index_argument);
+    fn eval_list_operand(&mut self, node: &ASTNode) -> JasonResult<Vec<Value>> {
+        let operator = node.token.plain();
+        let left = node.left.as_ref().ok_or_else(||
+            self.err(JasonErrorKind::MissingValue, format!("{} is missing the list on its left", operator)))?;
+        let values = match self.to_json(left)? {
+            Some(Value::Array(values)) => values,
+            Some(other) => {
+                let found = self.infer_type_from(&other)?;
+                return Err(self.err(
+                    JasonErrorKind::TypeError(left.plain_sum.clone()),
+                    format!("left side of {} must be of type List found {}", operator, found)
+                ));
+            },
+            None => return Err(self.err(JasonErrorKind::ValueError, format!("left side of {} is None", operator))),
+        };
+        Ok(values)
+    }
+
+    // evaluates the key after `by(item)` for every item
+    fn eval_keys(&mut self, node: &ASTNode, values: &[Value]) -> JasonResult<Option<Vec<Value>>> {
+        let Some(by) = node.right.as_ref().filter(|by| matches!(by.token.token_type, TokenType::By(_))) else {
+            return Ok(None);
+        };
+        let usage = format!("list {} by(item) key", node.token.plain());
+        let (name, key) = match (&by.token.token_type, by.right.as_ref()) {
+            (TokenType::By(args), Some(key)) if args.len() == 1 && args[0].token.token_type == TokenType::ID => (args[0].token.plain(), key),
+            _ => return Err(self.err(
+                JasonErrorKind::SyntaxErrorHere(by.plain_sum.clone()),
+                format!("by binds a single name. usage: {}", usage)
+            )),
+        };
+
+        let keys = self.with_scope(Scope::open(), |context| {
+            let mut keys: Vec<Value> = Vec::with_capacity(values.len());
+            for value in values {
+                context.bind(name.clone(), value.clone());
+                keys.push(context.to_json(key)?.ok_or_else(||
+                    context.err(JasonErrorKind::ValueError, format!("key {} is None", key.plain_sum)))?);
             }
+            Ok(keys)
+        })?;
+        Ok(Some(keys))
+    }
+
+    /// `list sort` and `list sort by(item) key`, ordered the way `<` compares values.
+    ///
+    /// The sort is stable, and keys that can't be compared with each other are a type error.
+    pub fn eval_sort(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let values = self.eval_list_operand(node)?;
+        let keys = match self.eval_keys(node, &values)? {
+            Some(keys) => keys,
+            None => values.clone(),
+        };
 
-            self.variables.remove(&argument);
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        order.sort_by(|&a, &b| Self::sort_order(&keys[a], &keys[b]));
+
+        // the sort order groups values by kind, so any pair that can't be compared ends up next to each other
+        let incomparable = order.windows(2).find(|pair| Self::compare_values(&keys[pair[0]], &keys[pair[1]]).is_none());
+        if let Some(&[a, b]) = incomparable {
+            let a_type = self.infer_type_from(&keys[a])?;
+            let b_type = self.infer_type_from(&keys[b])?;
+            return Err(self.err(
+                JasonErrorKind::TypeError(node.token.plain()),
+                format!("sort can't order {} and {}, only values of the same kind can be compared", a_type, b_type)
+            ));
         }
-        
-        Ok(Some(Value::Array(results)))
+
+        let mut values: Vec<Option<Value>> = values.into_iter().map(Some).collect();
+        Ok(Some(Value::Array(order.into_iter().filter_map(|i| values[i].take()).collect())))
     }
-    
+
+    /// `list unique` keeps the first of every set of equal items.
+    pub fn eval_unique(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let values = self.eval_list_operand(node)?;
+        let mut unique: Vec<Value> = Vec::with_capacity(values.len());
+        for value in values {
+            if !unique.iter().any(|seen| Self::values_equal(seen, &value)) {
+                unique.push(value);
+            }
+        }
+        Ok(Some(Value::Array(unique)))
+    }
+
+    pub fn eval_reverse(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let mut values = self.eval_list_operand(node)?;
+        values.reverse();
+        Ok(Some(Value::Array(values)))
+    }
+
+    /// `list group by(item) key` makes an object of lists, one for each key in order of first appearance.
+    pub fn eval_group(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let values = self.eval_list_operand(node)?;
+        let keys = self.eval_keys(node, &values)?.ok_or_else(||
+            self.err(JasonErrorKind::SyntaxError, "group needs a key. usage: list group by(item) key".to_string()))?;
+
+        let mut groups = Map::new();
+        for (key, value) in keys.into_iter().zip(values) {
+            // keys become object keys, so only values with an obvious text form can be used
+            let key = match key {
+                Value::String(key) => key,
+                Value::Number(_) | Value::Bool(_) | Value::Null => key.to_string(),
+                other => {
+                    let found = self.infer_type_from(&other)?;
+                    return Err(self.err(
+                        JasonErrorKind::TypeError(node.token.plain()),
+                        format!("group keys must be of type String, Number, Bool or Null found {}", found)
+                    ));
+                }
+            };
+            match groups.entry(key).or_insert_with(|| Value::Array(Vec::new())) {
+                Value::Array(group) => group.push(value),
+                _ => unreachable!("groups only hold lists"),
+            }
+        }
+        Ok(Some(Value::Object(groups)))
+    }
+
+    /// `list filter(n, i) cond` keeps the items `cond` is true for, `i` is optional and holds the index.
+    pub fn eval_filter(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let TokenType::Filter(args) = &node.token.token_type else {
+            return Err(self.err(JasonErrorKind::SyntaxError, "eval_filter called on a non filter token".to_string()));
+        };
+        let usage = "list filter(item) condition or list filter(item, index) condition";
+        if args.is_empty() || args.len() > 2 {
+            return Err(self.err(JasonErrorKind::ArityError(node.token.plain()), format!("filter takes 1 or 2 names but {} {} given. usage: {}", args.len(), if args.len() == 1 { "was" } else { "were" }, usage)));
+        }
+        let (values, names) = self.eval_list_operator(node, args, usage)?;
+        let condition = node.right.as_ref().ok_or_else(||
+            self.err(JasonErrorKind::MissingValue, "filter is missing its condition".to_string()))?;
+
+        let kept = self.with_scope(Scope::open(), |context| {
+            let mut kept: Vec<Value> = Vec::new();
+            for (i, value) in values.into_iter().enumerate() {
+                context.bind(names[0].clone(), value.clone());
+                if let Some(index) = names.get(1) {
+                    context.bind(index.clone(), Value::Number(i.into()));
+                }
+
+                match context.to_json(condition)? {
+                    Some(Value::Bool(true)) => kept.push(value),
+                    Some(Value::Bool(false)) => {},
+                    other => {
+                        let found = context.infer_type_from(&other.unwrap_or(Value::Null))?;
+                        return Err(context.err(
+                            JasonErrorKind::TypeError(condition.plain_sum.clone()),
+                            format!("filter condition must be of type Bool found {}", found)
+                        ));
+                    }
+                }
+            }
+            Ok(kept)
+        })?;
+        Ok(Some(Value::Array(kept)))
+    }
+
+    /// `list reduce(acc, n, i) expr from init` folds the list into one value, starting with `acc` as `init`.
+    pub fn eval_reduce(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let TokenType::Reduce(args) = &node.token.token_type else {
+            return Err(self.err(JasonErrorKind::SyntaxError, "eval_reduce called on a non reduce token".to_string()));
+        };
+        let usage = "list reduce(acc, item) expression from initial or list reduce(acc, item, index) expression from initial";
+        if args.len() < 2 || args.len() > 3 {
+            return Err(self.err(JasonErrorKind::ArityError(node.token.plain()), format!("reduce takes 2 or 3 names but {} {} given. usage: {}", args.len(), if args.len() == 1 { "was" } else { "were" }, usage)));
+        }
+        let (values, names) = self.eval_list_operator(node, args, usage)?;
+        let from = node.right.as_ref().ok_or_else(||
+            self.err(JasonErrorKind::MissingValue, format!("reduce is missing its expression. usage: {}", usage)))?;
+        let (Some(body), Some(init)) = (from.left.as_ref(), from.right.as_ref()) else {
+            return Err(self.err(JasonErrorKind::MissingValue, format!("reduce is missing its expression or initial value. usage: {}", usage)));
+        };
+
+        // the initial value is evaluated before any names are bound
+        let initial = self.to_json(init)?.ok_or_else(||
+            self.err(JasonErrorKind::ValueError, format!("initial value {} of reduce is None", init.plain_sum)))?;
+
+        let acc = self.with_scope(Scope::open(), |context| {
+            let mut acc = initial;
+            for (i, value) in values.into_iter().enumerate() {
+                context.bind(names[0].clone(), acc);
+                context.bind(names[1].clone(), value);
+                if let Some(index) = names.get(2) {
+                    context.bind(index.clone(), Value::Number(i.into()));
+                }
+                acc = context.to_json(body)?.ok_or_else(||
+                    context.err(JasonErrorKind::ValueError, format!("{} resulted in None", body.plain_sum)))?;
+            }
+            Ok(acc)
+        })?;
+        Ok(Some(acc))
+    }
+
     fn eval_double_colon(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
         let left = 
                 node.left
@@ -1171,7 +1433,7 @@ impl Context {
                 self.types.insert(left.token.plain(), typed_value);
                 Ok(None)
             },
-            TokenType::FnCall(args) => {
+            TokenType::FnCall(args) | TokenType::Builtin(args) => {
                 let typed_args = args
                         
                         .iter()
@@ -1230,7 +1492,107 @@ impl Context {
         }
     }
 
+    /// The string builtins, `args` have already been checked against their signature in `builtins`.
+    ///
+    /// Indexes and widths count characters rather than bytes.
+    pub fn eval_string_builtin(&self, name: &str, args: &[Value]) -> JasonResult<Value> {
+        let text = |i: usize| args.get(i).and_then(Value::as_str).unwrap_or_default();
+        let int = |i: usize| args.get(i).and_then(Value::as_i64);
+        let s = text(0);
+
+        let result = match name {
+            "upper" => Value::String(s.to_uppercase()),
+            "lower" => Value::String(s.to_lowercase()),
+            "trim" => Value::String(s.trim().to_string()),
+            "starts_with" => Value::Bool(s.starts_with(text(1))),
+            "ends_with" => Value::Bool(s.ends_with(text(1))),
+            "replace" => {
+                if text(1).is_empty() {
+                    return Err(self.err(JasonErrorKind::ValueError, "replace can't replace an empty string".to_string()));
+                }
+                Value::String(s.replace(text(1), text(2)))
+            },
+            // an empty separator splits into characters
+            "split" => {
+                let parts: Vec<Value> = if text(1).is_empty() {
+                    s.chars().map(|c| Value::String(c.to_string())).collect()
+                } else {
+                    s.split(text(1)).map(|part| Value::String(part.to_string())).collect()
+                };
+                Value::Array(parts)
+            },
+            "join" => {
+                let parts: Vec<&str> = args[0].as_array().map(|items| items.iter().filter_map(Value::as_str).collect()).unwrap_or_default();
+                Value::String(parts.join(text(1)))
+            },
+            "pad_left" => {
+                let fill = if args.len() > 2 { text(2) } else { " " };
+                let mut fill_chars = fill.chars();
+                let (Some(fill), None) = (fill_chars.next(), fill_chars.next()) else {
+                    return Err(self.err(JasonErrorKind::ValueError, format!("pad_left pads with a single character, \"{}\" isn't one", fill)));
+                };
+                let width = int(1).unwrap_or(0).max(0) as usize;
+                let padding = fill.to_string().repeat(width.saturating_sub(s.chars().count()));
+                Value::String(padding + s)
+            },
+            // a negative start counts from the end, and the result is cut short at the end of the string
+            "substr" => {
+                let chars: Vec<char> = s.chars().collect();
+                let len = chars.len() as i64;
+                let start = int(1).unwrap_or(0);
+                let start = if start < 0 { (len + start).max(0) } else { start.min(len) } as usize;
+                let end = match int(2) {
+                    Some(count) if count < 0 => {
+                        return Err(self.err(JasonErrorKind::ValueError, format!("substr length can't be negative, found {}", count)));
+                    },
+                    Some(count) => (start as i64 + count).min(len) as usize,
+                    None => chars.len(),
+                };
+                Value::String(chars[start..end].iter().collect())
+            },
+            _ => return Err(self.err(JasonErrorKind::UndefinedTemplate(name.to_string()), format!("{} is not a string builtin", name))),
+        };
+        Ok(result)
+    }
+
+    // compiles `pattern` the first time it's used, a bad pattern is a ValueError
+    fn regex(&mut self, pattern: &str) -> JasonResult<Regex> {
+        if let Some(regex) = self.regex_cache.get(pattern) {
+            return Ok(regex.clone());
+        }
+        let regex = Regex::new(pattern).map_err(|err|
+            self.err(JasonErrorKind::ValueError, format!("invalid regular expression \"{}\": {}", pattern, err)))?;
+        self.regex_cache.insert(pattern.to_string(), regex.clone());
+        Ok(regex)
+    }
+
+    /// `matches`, `captures` and `regex_replace`, `args` have already been checked against their signature in `builtins`.
+    pub fn eval_regex_builtin(&mut self, name: &str, args: &[Value]) -> JasonResult<Value> {
+        let text = |i: usize| args.get(i).and_then(Value::as_str).unwrap_or_default();
+        let regex = self.regex(text(1))?;
+        let s = text(0);
+
+        let result = match name {
+            "matches" => Value::Bool(regex.is_match(s)),
+            // the whole match then every group, groups that didn't take part are null
+            "captures" => match regex.captures(s) {
+                Some(captures) => Value::Array(
+                    captures.iter()
+                        .map(|group| group.map_or(Value::Null, |group| Value::String(group.as_str().to_string())))
+                        .collect()
+                ),
+                None => Value::Null,
+            },
+            "regex_replace" => Value::String(regex.replace_all(s, text(2)).into_owned()),
+            _ => return Err(self.err(JasonErrorKind::UndefinedTemplate(name.to_string()), format!("{} is not a regex builtin", name))),
+        };
+        Ok(result)
+    }
+
     pub fn value_to_string(&self, value: &Value) -> JasonResult<String> {
+        if let Some(name) = Template::find_reference(value) {
+            return Err(self.template_value_err(name, "turned into a string"));
+        }
         match value {
             Value::Number(n) => Ok(n.to_string()),
             Value::Bool(b) => Ok(b.to_string()),
@@ -1534,10 +1896,298 @@ impl Context {
             )
         )
     }
+    // only the branch that was picked gets evaluated
+    pub fn eval_if(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let condition_node = node.left.as_ref().ok_or_else(||
+            self.err(JasonErrorKind::MissingValue, "if expression is missing its condition".to_string()))?;
+        let branches = node.right.as_ref().ok_or_else(||
+            self.err(JasonErrorKind::MissingValue, "if expression is missing its branches".to_string()))?;
+
+        let condition = self.to_json(condition_node)?.ok_or_else(||
+            self.err(JasonErrorKind::ValueError, "if condition is None".to_string()))?;
+
+        let branch = match condition {
+            Value::Bool(true) => &branches.left,
+            Value::Bool(false) => &branches.right,
+            other => {
+                let found = self.infer_type_from(&other)?;
+                return Err(self.err(
+                    JasonErrorKind::TypeError(condition_node.plain_sum.clone()),
+                    format!("if condition must be of type Bool found {}", found),
+                ));
+            }
+        };
+
+        let branch = branch.as_ref().ok_or_else(||
+            self.err(JasonErrorKind::MissingValue, "if expression is missing a branch".to_string()))?;
+        self.to_json(branch)
+    }
+
+    // numbers compare by value so 1 == 1.0, everything else structurally
+    pub fn values_equal(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Number(_), Value::Number(_)) => Self::compare_values(a, b) == Some(std::cmp::Ordering::Equal),
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| Self::values_equal(a, b))
+            },
+            (Value::Object(a), Value::Object(b)) => {
+                a.len() == b.len() && a.iter().all(|(k, v)| b.get(k).is_some_and(|other| Self::values_equal(v, other)))
+            },
+            _ => a == b,
+        }
+    }
+
+    // ordering between two values of the same kind, None when they can't be ordered
+    pub fn compare_values(a: &Value, b: &Value) -> Option<std::cmp::Ordering> {
+        match (a, b) {
+            (Value::Null, Value::Null) => Some(std::cmp::Ordering::Equal),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::Number(n1), Value::Number(n2)) => {
+                if let (Some(a), Some(b)) = (n1.as_i64(), n2.as_i64()) {
+                    return Some(a.cmp(&b));
+                }
+                n1.as_f64()?.partial_cmp(&n2.as_f64()?)
+            },
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (Value::Array(a), Value::Array(b)) => {
+                for (a, b) in a.iter().zip(b) {
+                    match Self::compare_values(a, b)? {
+                        std::cmp::Ordering::Equal => continue,
+                        ordering => return Some(ordering),
+                    }
+                }
+                Some(a.len().cmp(&b.len()))
+            },
+            _ => None,
+        }
+    }
+
+    // a total order for sorting, values that `compare_values` can't order are put in order of their kind
+    fn sort_order(a: &Value, b: &Value) -> std::cmp::Ordering {
+        let kind = |value: &Value| match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        };
+        match (a, b) {
+            (Value::Array(a), Value::Array(b)) => {
+                for (a, b) in a.iter().zip(b) {
+                    match Self::sort_order(a, b) {
+                        std::cmp::Ordering::Equal => continue,
+                        ordering => return ordering,
+                    }
+                }
+                a.len().cmp(&b.len())
+            },
+            _ => Self::compare_values(a, b).unwrap_or_else(|| kind(a).cmp(&kind(b))),
+        }
+    }
+
+    pub fn eval_comparison(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let left_node = node.left.as_ref().ok_or_else(||
+            self.err(JasonErrorKind::MissingValue, "left side of the comparison is missing".to_string()))?;
+        // a comparator without a left side is an interval like `>= 0`, which only means something as a type
+        if left_node.token.token_type == TokenType::Empty {
+            return Err(self.err(
+                JasonErrorKind::TypeError(node.token.plain()),
+                format!("interval {} can only be used as a type, compare against a value with <value> {}", node.plain_sum.trim(), node.plain_sum.trim())
+            ));
+        }
+        let right_node = node.right.as_ref().ok_or_else(||
+            self.err(JasonErrorKind::MissingValue, "right side of the comparison is missing".to_string()))?;
+
+        let left = self.to_json(left_node)?.ok_or_else(||
+            self.err(JasonErrorKind::ValueError, "left value is None".to_string()))?;
+        let right = self.to_json(right_node)?.ok_or_else(||
+            self.err(JasonErrorKind::ValueError, "right value is None".to_string()))?;
+
+        let result = match &node.token.token_type {
+            TokenType::DoubleEquals => Self::values_equal(&left, &right),
+            TokenType::NotEquals => !Self::values_equal(&left, &right),
+            op => {
+                let ordering = match Self::compare_values(&left, &right) {
+                    Some(ordering) => ordering,
+                    None => {
+                        let left_type = self.infer_type_from(&left)?;
+                        let right_type = self.infer_type_from(&right)?;
+                        return Err(self.err(
+                            JasonErrorKind::TypeError(node.token.plain()),
+                            format!("can't compare {} {} {}", left_type, node.token.plain(), right_type)
+                        ));
+                    }
+                };
+                match op {
+                    TokenType::LessThan => ordering.is_lt(),
+                    TokenType::GreaterThan => ordering.is_gt(),
+                    TokenType::LessThanEqualTo => ordering.is_le(),
+                    _ => ordering.is_ge(),
+                }
+            }
+        };
+        Ok(Some(Value::Bool(result)))
+    }
+
+    fn eval_bool_operand(&mut self, node: &ASTNode, op: &ASTNode) -> JasonResult<bool> {
+        let value = self.to_json(node)?.ok_or_else(||
+            self.err(JasonErrorKind::ValueError, format!("operand of {} is None", op.token.plain())))?;
+        match value {
+            Value::Bool(b) => Ok(b),
+            other => {
+                let found = self.infer_type_from(&other)?;
+                Err(self.err(
+                    JasonErrorKind::TypeError(node.plain_sum.clone()),
+                    format!("operands of {} must be of type Bool found {}", op.token.plain(), found)
+                ))
+            }
+        }
+    }
+
+    // `and` and `or` short circuit, the right side is only evaluated when it decides the result
+    pub fn eval_logic(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let (left_node, right_node) = match (node.left.as_ref(), node.right.as_ref()) {
+            (Some(left), Some(right)) => (left, right),
+            _ => return Err(self.err(JasonErrorKind::MissingValue, format!("{} expects a value on both sides", node.token.plain()))),
+        };
+
+        let left = self.eval_bool_operand(left_node, node)?;
+        let result = match (&node.token.token_type, left) {
+            (TokenType::And, false) => false,
+            (TokenType::Or, true) => true,
+            _ => self.eval_bool_operand(right_node, node)?,
+        };
+        Ok(Some(Value::Bool(result)))
+    }
+
+    pub fn eval_not(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let right_node = node.right.as_ref().ok_or_else(||
+            self.err(JasonErrorKind::MissingValue, "not expects a value".to_string()))?;
+        let value = self.eval_bool_operand(right_node, node)?;
+        Ok(Some(Value::Bool(!value)))
+    }
+
+    // `a.b.c` and `a[0]` are evaluated as one chain so errors can name the whole path
+    pub fn eval_path(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let mut segments = Vec::new();
+        let mut base = node;
+        while matches!(base.token.token_type, TokenType::Dot | TokenType::QuestionDot | TokenType::Index(_) | TokenType::OptionalIndex(_)) {
+            segments.push(base);
+            base = base.left.as_ref().ok_or_else(||
+                self.err(JasonErrorKind::MissingValue, format!("{} is missing the value it accesses", node.plain_sum)))?;
+        }
+        segments.reverse();
+
+        let mut value = self.to_json(base)?.ok_or_else(||
+            self.err(JasonErrorKind::ValueError, format!("{} is None", base.plain_sum)))?;
+
+        for segment in segments {
+            let optional = matches!(segment.token.token_type, TokenType::QuestionDot | TokenType::OptionalIndex(_));
+            // once an optional access comes up empty the rest of the path is skipped
+            if optional && value.is_null() {
+                return Ok(Some(Value::Null));
+            }
+            if let Some(name) = Template::referenced(&value) {
+                return Err(self.template_value_err(name, "accessed with a path"));
+            }
+
+            let key = match &segment.token.token_type {
+                TokenType::Index(args) | TokenType::OptionalIndex(args) if args.len() == 1 => self.to_json(&args[0])?.ok_or_else(||
+                    self.err(JasonErrorKind::ValueError, format!("index {} is None", args[0].plain_sum)))?,
+                TokenType::Index(args) | TokenType::OptionalIndex(args) => return Err(self.err(
+                    JasonErrorKind::IndexError,
+                    format!("{} failed, an index takes exactly one value found {}", node.plain_sum, args.len())
+                )),
+                _ => {
+                    let key = segment.right.as_ref().ok_or_else(||
+                        self.err(JasonErrorKind::MissingKey, format!("{} is missing a key", segment.plain_sum)))?;
+                    Value::String(key.token.plain())
+                }
+            };
+            if optional && Self::missing_entry(&value, &key) {
+                return Ok(Some(Value::Null));
+            }
+            let prefix = segment.left.as_ref().map_or_else(String::new, |left| left.plain_sum.clone());
+            value = self.index_value(value, &key, &prefix, &node.plain_sum)?;
+        }
+        Ok(Some(value))
+    }
+
+    // a key or index that isn't there, indexing the wrong kind of value is still an error for `?.`
+    fn missing_entry(value: &Value, key: &Value) -> bool {
+        let in_bounds = |n: &Number, len: usize| n.as_i64().is_some_and(|i| i >= 0 && (i as usize) < len);
+        match (value, key) {
+            (Value::Object(map), Value::String(k)) => !map.contains_key(k),
+            (Value::Array(items), Value::Number(n)) => !in_bounds(n, items.len()),
+            (Value::String(s), Value::Number(n)) => !in_bounds(n, s.chars().count()),
+            _ => false,
+        }
+    }
+
+    // the right side is only evaluated when the left side is null
+    pub fn eval_coalesce(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let (left_node, right_node) = match (node.left.as_ref(), node.right.as_ref()) {
+            (Some(left), Some(right)) => (left, right),
+            _ => return Err(self.err(JasonErrorKind::MissingValue, "?? expects a value on both sides".to_string())),
+        };
+
+        match self.to_json(left_node)? {
+            Some(Value::Null) | None => self.to_json(right_node),
+            value => Ok(value),
+        }
+    }
+
+    fn index_value(&mut self, value: Value, key: &Value, prefix: &str, path: &str) -> JasonResult<Value> {
+        match (value, key) {
+            (Value::Object(mut map), Value::String(k)) => map.remove(k).ok_or_else(|| {
+                let keys: Vec<&str> = map.keys().map(|k| k.as_str()).collect();
+                self.err(
+                    JasonErrorKind::IndexError,
+                    format!("{} failed, {} has no key \"{}\" (keys: {})", path, prefix, k, keys.join(", "))
+                )
+            }),
+            (Value::Array(mut items), Value::Number(n)) => {
+                let len = items.len();
+                match n.as_i64() {
+                    Some(i) if i >= 0 && (i as usize) < len => Ok(items.swap_remove(i as usize)),
+                    _ => Err(self.err(
+                        JasonErrorKind::IndexError,
+                        format!("{} failed, index {} is out of bounds for {} with length {}", path, n, prefix, len)
+                    )),
+                }
+            },
+            (Value::String(s), Value::Number(n)) => {
+                let len = s.chars().count();
+                match n.as_i64() {
+                    Some(i) if i >= 0 && (i as usize) < len => Ok(Value::String(s.chars().nth(i as usize).unwrap_or_default().to_string())),
+                    _ => Err(self.err(
+                        JasonErrorKind::IndexError,
+                        format!("{} failed, index {} is out of bounds for {} with length {}", path, n, prefix, len)
+                    )),
+                }
+            },
+            (value, key) => {
+                let value_type = self.infer_type_from(&value)?;
+                let key_type = self.infer_type_from(key)?;
+                Err(self.err(
+                    JasonErrorKind::IndexError,
+                    format!("{} failed, {} is {} and can't be indexed by {} {}", path, prefix, value_type, key_type, key)
+                ))
+            }
+        }
+    }
+
     pub fn to_json(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
         match &node.token.token_type {
             TokenType::Null => Ok(Some(serde_json::Value::Null)),
             TokenType::Map(_) => self.eval_map(node),
+            TokenType::Filter(_) => self.eval_filter(node),
+            TokenType::Reduce(_) => self.eval_reduce(node),
+            TokenType::Sort => self.eval_sort(node),
+            TokenType::Unique => self.eval_unique(node),
+            TokenType::Reverse => self.eval_reverse(node),
+            TokenType::Group => self.eval_group(node),
 
             TokenType::Plus  => self.eval_plus(node),
             TokenType::Minus => self.eval_minus(node),
@@ -1549,6 +2199,12 @@ impl Context {
             TokenType::Repeat => self.eval_repeat(node),
             TokenType::Merge => self.eval_merge(node),
             TokenType::At => self.eval_at(node),
+            TokenType::Dot | TokenType::QuestionDot | TokenType::Index(_) | TokenType::OptionalIndex(_) => self.eval_path(node),
+            TokenType::DoubleQuestion => self.eval_coalesce(node),
+            TokenType::Spread => Err(self.err(
+                JasonErrorKind::InvalidOperation(node.plain_sum.clone()),
+                format!("{} can only be used in the arguments of a template", node.plain_sum)
+            )),
             TokenType::Pick => self.eval_pick(node),
             TokenType::UPick => self.eval_upick(node),
             TokenType::DoubleColon => self.eval_double_colon(node),
@@ -1557,11 +2213,15 @@ impl Context {
             TokenType::IntConverion(_) => self.eval_int_conversion(node),
             TokenType::FloatConverion(_) => self.eval_float_conversion(node),
             TokenType::ID => {
-                if !self.variables.contains_key(&node.token.plain()) {
-                    return Err(JasonError::new(JasonErrorKind::UndefinedVariable(node.token.plain()), self.source_path.clone(),self.local_root.clone(),
-                        format!("the variable {} does not exist in file {}", node.token.plain(), self.source_path.clone())));
+                if let Some(value) = self.lookup(&node.token.plain()) {
+                    return Ok(Some(value.clone()));
                 }
-                Ok(Some(self.variables.get(&node.token.plain()).unwrap().clone()))
+                // a template named without calling it is passed around as a value
+                if self.templates.contains_key(&node.token.plain()) {
+                    return Ok(Some(Template::reference(&node.token.plain())));
+                }
+                Err(JasonError::new(JasonErrorKind::UndefinedVariable(node.token.plain()), self.source_path.clone(),self.local_root.clone(),
+                    format!("the variable {} does not exist in file {}", node.token.plain(), self.source_path.clone())))
             },
             TokenType::BoolLiteral(value) => {
                 Ok(Some(serde_json::Value::Bool(value.clone())))
@@ -1602,6 +2262,15 @@ impl Context {
                 Ok(Some(Value::Array(json_values)))
             }
             TokenType::From => self.eval_from(node),
+            TokenType::If => self.eval_if(node),
+            TokenType::LessThan           |
+            TokenType::GreaterThan        |
+            TokenType::LessThanEqualTo    |
+            TokenType::GreaterThanEqualTo |
+            TokenType::DoubleEquals       |
+            TokenType::NotEquals => self.eval_comparison(node),
+            TokenType::And | TokenType::Or => self.eval_logic(node),
+            TokenType::Not => self.eval_not(node),
 
 
             TokenType::Info => {
@@ -1649,9 +2318,13 @@ impl Context {
                  if let Some(right_node) = node.right.as_ref() {
                     if let TokenType::StringLiteral(path) = &right_node.token.token_type {
                         let file_path = Path::new(path);
+                        self.dependencies.borrow_mut().insert(path.clone());
                         if file_path.exists() {
-                            let value = jason_to_json(path)?;
-                            return Ok(Some(value));
+                            // included files get their own lua instance and import stack
+                            let lua = Rc::new(RefCell::new(LuaInstance::new()?));
+                            let import_stack = Rc::new(RefCell::new(HashSet::new()));
+                            let context = jason_hidden::jason_context_from_file_with_imports(path.clone(), lua, import_stack, self.dependencies.clone())?;
+                            return Ok(Some(context.out));
                         } else {
 
                             return Err(self.err(JasonErrorKind::Custom, format!("failed to find path {}", path)));
@@ -1666,15 +2339,26 @@ impl Context {
             },
             TokenType::Out => {
                 if let Some(right_node) = node.right.as_ref() {
-                    self.out = self.to_json(right_node)?.ok_or_else(||
+                    let out = self.to_json(right_node)?.ok_or_else(||
                         JasonError::new(JasonErrorKind::ValueError, self.source_path.clone(), self.local_root.clone(), "out value is None"))?;
+                    if let Some(name) = Template::find_reference(&out) {
+                        return Err(self.template_value_err(name, "part of the output"));
+                    }
+                    self.out = out;
+                    self.out_node = self.local_root.clone();
                     return Ok(None);
                 }
                 Err(JasonError::new(JasonErrorKind::SyntaxError, self.source_path.clone(), self.local_root.clone(),
                     "out statement must have valid jason expression.\n example: out \"Hello!\""))
             },
-            TokenType::TemplateDef(args, block) => {
+            TokenType::TemplateDef(args, block, parent) => {
                 let args = args;
+                if self.functions.contains_key(&node.token.plain()) {
+                    return Err(self.err(
+                        JasonErrorKind::InvalidOperation(node.token.plain()),
+                        format!("template {} has the same name as a function, give one of them another name", node.token.plain())
+                    ));
+                }
                 if args.len() > 0 {
                     self.templates.insert(
                         node.token.plain(), 
@@ -1684,7 +2368,8 @@ impl Context {
                             //fix these clones xd
                             args.clone(), 
                             block.clone(), 
-                            self.template_types.get(&node.token.plain()).cloned()
+                            self.template_types.get(&node.token.plain()).cloned(),
+                            parent.clone()
                         )?
                     );
                     return Ok(None);
@@ -1699,18 +2384,42 @@ impl Context {
                         node.token.plain(), 
                         Vec::new(), 
                         block.clone(), 
-                        self.template_types.get(&node.token.plain()).cloned()
+                        self.template_types.get(&node.token.plain()).cloned(),
+                        parent.clone()
                     )?
                 );
                 return Ok(None);
             },
             TokenType::LuaFnCall(_) => self.eval_lua_fn(node),
+            TokenType::Let => self.eval_let(node),
+            TokenType::Builtin(args) => {
+                // templates and functions with a builtin's name replace it
+                if let Some(function) = self.functions.get(&node.token.plain()).cloned() {
+                    return function.call(self, args);
+                }
+                if let Some(template) = self.templates.get(&node.token.plain()).cloned() {
+                    return template.resolve(self, args);
+                }
+                builtins::call(self, &node.token.plain(), args)
+            },
+            TokenType::FnDef(params) => {
+                if self.templates.contains_key(&node.token.plain()) {
+                    return Err(self.err(
+                        JasonErrorKind::InvalidOperation(node.token.plain()),
+                        format!("function {} has the same name as a template, give one of them another name", node.token.plain())
+                    ));
+                }
+                let body = node.right.as_deref().cloned().ok_or_else(||
+                    self.err(JasonErrorKind::MissingValue, format!("function {} is missing its body", node.token.plain())))?;
+                let function = Function::new(self, node.token.plain(), params.clone(), body, self.template_types.get(&node.token.plain()).cloned())?;
+                self.functions.insert(node.token.plain(), function);
+                Ok(None)
+            },
             TokenType::FnCall(args) => { 
-                if !self.templates.contains_key(&node.token.plain()) {
-                    return Err(JasonError::new(JasonErrorKind::UndefinedTemplate(node.token.plain()), self.source_path.clone(), self.local_root.clone(), format!("the template {} does not exist in file {}", node.token.plain(), self.source_path)));
+                if let Some(function) = self.functions.get(&node.token.plain()).cloned() {
+                    return function.call(self, args);
                 }
-
-                let template = self.templates.get(&node.token.plain()).unwrap().clone();
+                let template = self.find_template(&node.token.plain())?;
                 template.resolve(self, &args)
             },
             token => {
@@ -1720,6 +2429,50 @@ impl Context {
         }
     }
     
+    /// Finds a variable in the innermost scopes first, up to the first closed one, then at the top level.
+    pub fn lookup(&self, name: &str) -> Option<&serde_json::Value> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(value) = scope.variables.get(name) {
+                return Some(value);
+            }
+            if scope.closed {
+                break;
+            }
+        }
+        self.variables.get(name)
+    }
+
+    /// Binds a variable in the innermost scope, or at the top level when there is none.
+    pub fn bind(&mut self, key: String, value: serde_json::Value) {
+        match self.scopes.last_mut() {
+            Some(scope) => scope.variables.insert(key, value),
+            None => self.variables.insert(key, value),
+        };
+    }
+
+    /// Evaluates `eval` inside `scope`, the scope is dropped afterwards even when `eval` fails.
+    pub fn with_scope<T>(&mut self, scope: Scope, eval: impl FnOnce(&mut Self) -> JasonResult<T>) -> JasonResult<T> {
+        self.scopes.push(scope);
+        let result = eval(self);
+        self.scopes.pop();
+        result
+    }
+
+    // how many templates and functions are being evaluated inside each other
+    pub fn call_depth(&self) -> usize {
+        self.scopes.iter().filter(|scope| scope.closed).count()
+    }
+
+    /// Looks up a template by name, or through a variable holding a template reference.
+    pub fn find_template(&self, name: &str) -> JasonResult<Template> {
+        if let Some(template) = self.templates.get(name) {
+            return Ok(template.clone());
+        }
+        let referenced = self.lookup(name).and_then(Template::referenced).unwrap_or(name);
+        self.templates.get(referenced).cloned().ok_or_else(||
+            JasonError::new(JasonErrorKind::UndefinedTemplate(referenced.to_string()), self.source_path.clone(), self.local_root.clone(), format!("the template {} does not exist in file {}", referenced, self.source_path)))
+    }
+
     pub fn import_from_base(&mut self, key: &str) -> mlua::Result<()> {
         let lua_instance = self.lua_instance.borrow();
         let val: mlua::Value = lua_instance.base_env.get(key)?;
@@ -1741,11 +2494,47 @@ impl Context {
         */
         Ok(())
     }
+    // `let name = value in body`, name is only bound while evaluating body
+    fn eval_let(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        let Some(body) = node.right.as_ref() else {
+            return Err(self.err(
+                JasonErrorKind::InvalidOperation(node.plain_sum.clone()),
+                format!("{} needs `in` outside of a block, e.g. let x = 1 in x + 1", node.plain_sum)
+            ));
+        };
+        let (name, value) = self.eval_let_binding(node)?;
+        self.with_scope(Scope::open(), |context| {
+            context.bind(name, value);
+            context.to_json(body)
+        })
+    }
+
+    fn eval_let_binding(&mut self, node: &ASTNode) -> JasonResult<(String, serde_json::Value)> {
+        let binding = node.left.as_ref().ok_or_else(||
+            self.err(JasonErrorKind::MissingValue, format!("{} is missing its binding", node.plain_sum)))?;
+        let name = binding.left.as_ref().map(|name| name.token.plain()).unwrap_or_default();
+        let value_node = binding.right.as_ref().ok_or_else(||
+            self.err(JasonErrorKind::MissingValue, format!("{} is missing its value", node.plain_sum)))?;
+        let value = self.to_json(value_node)?.ok_or_else(||
+            self.err(JasonErrorKind::ValueError, format!("value of {} is None", name)))?;
+        Ok((name, value))
+    }
+
     pub fn block_to_json(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
+        // `let` bindings in the block are visible to the fields after them, but not outside of it
+        self.with_scope(Scope::open(), |context| context.block_fields_to_json(node))
+    }
+
+    fn block_fields_to_json(&mut self, node: &ASTNode) -> JasonResult<Option<serde_json::Value>> {
         if let TokenType::Block(args) = &node.token.token_type {
             let nodes = args;
             let mut map = Map::new(); // this will become our JSON object
             for node in nodes {
+                if node.token.token_type == TokenType::Let && node.right.is_none() {
+                    let (name, value) = self.eval_let_binding(node)?;
+                    self.bind(name, value);
+                    continue;
+                }
                 if node.token.token_type == TokenType::Colon {
                     let key_node = node.left.as_ref().ok_or_else(||
                         JasonError::new(JasonErrorKind::MissingKey, self.source_path.clone(), self.local_root.clone(), "Missing key"))?;
@@ -1771,16 +2560,6 @@ impl Context {
         }
     }
 
-    pub fn add_var(&mut self, key: String, value: serde_json::Value, typing: JasonType) {
-        self.variable_types.insert(key.clone(), typing);
-        self.variables.insert(key, value);
-    }
-
-    pub fn remove_var(&mut self, key: String) {
-        self.variable_types.remove(&key);
-        self.variables.remove(&key);
-    }
-
     pub fn export(&self, args: Vec<String>) -> Vec<ExportType> {
         let mut exported_values:Vec<ExportType> = Vec::new(); 
 
@@ -1814,6 +2593,12 @@ impl Context {
                 continue;
             }
 
+            if self.functions.contains_key(arg) {
+                let function = self.functions.get(arg).unwrap().clone();
+                exported_values.push(ExportType::Function(arg.clone(), function));
+                continue;
+            }
+
             if self.variable_types.contains_key(arg) {
                 let variable = self.variable_types.get(arg).unwrap().clone();
                 exported_values.push(ExportType::VariableType(arg.clone(), variable));
@@ -1845,6 +2630,9 @@ impl Context {
         for (name, value) in self.templates.clone() {
                 exported_values.push(ExportType::Template(name, value));
         }
+        for (name, value) in self.functions.clone() {
+            exported_values.push(ExportType::Function(name, value));
+        }
         for (name, value) in self.variable_types.clone() {
             exported_values.push(ExportType::VariableType(name, value));
         }
@@ -1863,6 +2651,9 @@ impl Context {
                 ExportType::Template(name, template) => {
                     self.templates.insert(name, template);
                 },
+                ExportType::Function(name, function) => {
+                    self.functions.insert(name, function);
+                },
                 ExportType::Variable(name, variable) => {
                     self.variables.insert(name, variable);
                 },
@@ -1879,6 +2670,14 @@ impl Context {
         }
     }
 
+    // template references only mean something to `map` and calls, they can't be looked into like objects
+    pub fn template_value_err(&self, name: &str, action: &str) -> JasonError {
+        self.err(
+            JasonErrorKind::TypeError(name.to_string()),
+            format!("template {} can't be {}, call it with {}(...) to get its value", name, action, name)
+        )
+    }
+
     pub fn err(&self, error_kind: JasonErrorKind, msg: String) -> JasonError {
         JasonError::new(
             error_kind, 
@@ -0,0 +1,103 @@
+use crate::astnode::ASTNode;
+use crate::context::{Context, Scope};
+use crate::jason_errors::JasonError;
+use crate::jason_errors::JasonErrorKind;
+use crate::jason_errors::JasonResult;
+use crate::jason_types::JasonType;
+use crate::token;
+use crate::token::TokenType;
+use std::rc::Rc;
+
+// how deep functions may call each other before giving up, recursion needs an `if` to stop it.
+// in unoptimized builds a call costs around 100KB of stack (3 or 4 `to_json` frames of ~28KB each),
+// and the deepest bodies measured overflowed a 2MB thread stack at 18 calls, so this leaves room to spare
+pub const MAX_CALL_DEPTH: usize = 12;
+
+/// A function defined with `name(params) => expr`.
+///
+/// Unlike a template its body is any expression, so it can result in numbers, strings or lists.
+#[derive(Debug, Clone)]
+pub struct Function {
+    name: String,
+    params: Vec<String>,
+    body: ASTNode,
+    typing: Option<(Vec<JasonType>, JasonType)>,
+}
+
+impl Function {
+    pub fn new(context: &Context, name: String, params: token::Args, body: ASTNode, typing: Option<(Vec<JasonType>, JasonType)>) -> JasonResult<Self> {
+        let mut names: Vec<String> = Vec::new();
+        for param in params {
+            if param.token.token_type != TokenType::ID {
+                return Err(
+                    JasonError::new(JasonErrorKind::SyntaxErrorHere(param.plain_sum.clone()), context.source_path.clone(), Some(Rc::new(param.clone())), "function parameters must be IDs".to_string())
+                )
+            }
+            if names.contains(&param.token.plain()) {
+                return Err(
+                    JasonError::new(JasonErrorKind::SyntaxErrorHere(param.plain_sum.clone()), context.source_path.clone(), Some(Rc::new(param.clone())), format!("parameter {} appears more than once in function {}", param.token.plain(), name))
+                )
+            }
+            names.push(param.token.plain());
+        }
+
+        Ok(Self { name, params: names, body, typing })
+    }
+
+    /// Evaluates the arguments in the caller's scope, then the body in a scope of its own
+    /// that only sees the parameters and top level variables.
+    pub fn call(&self, context: &mut Context, arguments: &token::Args) -> JasonResult<Option<serde_json::Value>> {
+        if arguments.len() != self.params.len() {
+            return Err(context.err(
+                JasonErrorKind::ArityError(self.name.clone()),
+                format!("function {} takes {} argument{} but {} {} given",
+                    self.name, self.params.len(), if self.params.len() == 1 { "" } else { "s" }, arguments.len(), if arguments.len() == 1 { "was" } else { "were" })
+            ));
+        }
+        if context.call_depth() >= MAX_CALL_DEPTH {
+            return Err(context.err(
+                JasonErrorKind::RecursionLimit(self.name.clone()),
+                format!("function {} went past the call depth limit of {}, make sure its recursion has a base case", self.name, MAX_CALL_DEPTH)
+            ));
+        }
+
+        let (param_types, result_type) = match &self.typing {
+            Some((param_types, result_type)) => (param_types.clone(), result_type.clone()),
+            None => (Vec::new(), JasonType::Any),
+        };
+
+        let mut values: Vec<(String, serde_json::Value)> = Vec::new();
+        for (i, (param, node)) in self.params.iter().zip(arguments).enumerate() {
+            let value = context.to_json(node)?.ok_or_else(||
+                context.err(JasonErrorKind::ValueError, format!("argument {} passed to function {} is None", node.plain_sum, self.name)))?;
+
+            let typed_param = param_types.get(i).cloned().unwrap_or(JasonType::Any);
+            if !typed_param.matches(&value) {
+                let infered_type = context.infer_type_from(&value)?;
+                return Err(context.err(
+                    JasonErrorKind::TypeError(param.clone()),
+                    format!("expected type {} for {} found {} in function {}", typed_param, param, infered_type, self.name),
+                ));
+            }
+            values.push((param.clone(), value));
+        }
+
+        let result = context.with_scope(Scope::closed(), |context| {
+            for (param, value) in values {
+                context.bind(param, value);
+            }
+            context.to_json(&self.body)
+        })?.ok_or_else(||
+            context.err(JasonErrorKind::ValueError, format!("function {} resulted in None", self.name)))?;
+
+        if !result_type.matches(&result) {
+            let infered_type = context.infer_type_from(&result)?;
+            return Err(context.err(
+                JasonErrorKind::TypeError(self.name.clone()),
+                format!("function {} resulted in {} expected {}", self.name, infered_type, result_type)
+            ));
+        }
+
+        Ok(Some(result))
+    }
+}
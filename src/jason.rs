@@ -1,14 +1,48 @@
-use crate::jason_hidden::{compile_jason_from_src, compile_jason_from_file};
+use crate::jason_hidden::{compile_jason_from_src, compile_jason_from_file, compile_jason_to_toml_from_src, compile_jason_to_toml_from_file};
+use crate::jason_hidden::{check_jason_from_src, check_jason_from_file, flatten_errors};
 use crate::lua_instance::LuaInstance;
+use crate::jason_yaml::value_to_yaml;
+use crate::jason_watch::JasonWatcher;
 use crate::jason_errors::{JasonError};
 use std::rc::Rc;
 use std::cell::RefCell;
 
 pub type CompilerResult<T> = Result<T, JasonError>;
 
+/// Outcome of checking a `.jason` source without producing any output.
+pub struct CheckReport {
+    /// Path of the checked file, or `direct source` for raw source.
+    pub file: String,
+    /// Every error found, with bundled errors split out individually.
+    pub errors: Vec<JasonError>,
+}
+
+impl CheckReport {
+    /// Returns true if the source compiled without errors.
+    pub fn passed(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Output formats a compiled `.jason` file can be rendered to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    PrettyJson,
+    Yaml,
+    Toml,
+}
+
+// Lua dependencies remember where they came from so watch mode can reload files
+#[derive(Clone)]
+pub(crate) enum LuaSource {
+    File { path: String, src: String },
+    Inline(String),
+}
+
 /// Builder for constructing Jason parsing with optional Lua dependencies.
 pub struct JasonBuilder {
-    lua_src: String,
+    lua_sources: Vec<LuaSource>,
 }
 
 impl JasonBuilder {
@@ -21,7 +55,17 @@ impl JasonBuilder {
     /// let builder = JasonBuilder::new();
     /// ```
     pub fn new() -> Self {
-        JasonBuilder { lua_src: String::new() }   
+        JasonBuilder { lua_sources: Vec::new() }   
+    }
+
+    fn lua_src(&self) -> String {
+        self.lua_sources
+            .iter()
+            .map(|source| match source {
+                LuaSource::File { src, .. } => src.as_str(),
+                LuaSource::Inline(src) => src.as_str(),
+            })
+            .collect()
     }
 
     /// Includes a Lua file as a dependency for `.jason` parsing.
@@ -37,13 +81,13 @@ impl JasonBuilder {
     /// use jason_rs::JasonBuilder;
     /// let builder = JasonBuilder::new().include_lua_file("scripts/helpers.lua").unwrap();
     /// ```
-    pub fn include_lua_file(mut self, file_path: &'static str) -> CompilerResult<JasonBuilder> {
+    pub fn include_lua_file(mut self, file_path: &str) -> CompilerResult<JasonBuilder> {
         let src = std::fs::read_to_string(file_path);
         if !src.is_ok() {
             return Err(JasonError::new(crate::jason_errors::JasonErrorKind::FileError, Rc::new(file_path.to_string()), None, format!("failed to read from file {}", file_path)));
         }
         let src = src.unwrap(); 
-        self.lua_src.push_str(&src); 
+        self.lua_sources.push(LuaSource::File { path: file_path.to_string(), src }); 
         Ok(self)
     }
 
@@ -59,7 +103,7 @@ impl JasonBuilder {
     /// let builder = JasonBuilder::new().include_lua(lua_code)?;
     /// ```
     pub fn include_lua(mut self, src: &'static str) -> CompilerResult<JasonBuilder> {
-        self.lua_src.push_str(&src); 
+        self.lua_sources.push(LuaSource::Inline(src.to_string())); 
         Ok(self)
     }
 
@@ -79,7 +123,7 @@ impl JasonBuilder {
     /// ```
     
     pub fn jason_to_json(self, file_path: &str) -> CompilerResult<serde_json::Value> {
-        let lua = Rc::new(RefCell::new(LuaInstance::new_with_src(self.lua_src)?));
+        let lua = Rc::new(RefCell::new(LuaInstance::new_with_src(self.lua_src())?));
         let json = compile_jason_from_file(file_path, lua)?;
         Ok(json)
     }
@@ -100,10 +144,150 @@ impl JasonBuilder {
     /// println!("{}", json);
     /// ```
     pub fn jason_src_to_json(self, src: &str) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        let lua = Rc::new(RefCell::new(LuaInstance::new_with_src(self.lua_src)?));
+        let lua = Rc::new(RefCell::new(LuaInstance::new_with_src(self.lua_src())?));
         let json = compile_jason_from_src(src, lua)?;
         Ok(json)
     }
+
+    /// Converts a `.jason` file into a YAML document using the Lua dependencies included in the builder.
+    ///
+    /// # Arguments
+    /// * `file_path` - Path to the `.jason` file.
+    ///
+    /// # Errors
+    /// Returns an error if reading or parsing the `.jason` file fails.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use jason_rs::JasonBuilder;
+    /// let yaml = JasonBuilder::new().jason_to_yaml("Deployment.jason").unwrap();
+    /// println!("{}", yaml);
+    /// ```
+    pub fn jason_to_yaml(self, file_path: &str) -> CompilerResult<String> {
+        let json = self.jason_to_json(file_path)?;
+        Ok(value_to_yaml(&json))
+    }
+
+    /// Converts raw `.jason` source into a YAML document using the Lua dependencies included in the builder.
+    ///
+    /// # Arguments
+    /// * `src` - `.jason` source code as a string.
+    ///
+    /// # Errors
+    /// Returns an error if parsing fails.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use jason_rs::JasonBuilder;
+    /// let src = r#"out {name: "alex", age: 20}"#;
+    /// let yaml = JasonBuilder::new().jason_src_to_yaml(src)?;
+    /// println!("{}", yaml);
+    /// ```
+    pub fn jason_src_to_yaml(self, src: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let json = self.jason_src_to_json(src)?;
+        Ok(value_to_yaml(&json))
+    }
+
+    /// Converts a `.jason` file into a TOML document using the Lua dependencies included in the builder.
+    ///
+    /// # Arguments
+    /// * `file_path` - Path to the `.jason` file.
+    ///
+    /// # Errors
+    /// Returns an error if reading or parsing the `.jason` file fails, or if the `out` value
+    /// can't be expressed in TOML (a top-level non-object, `null`, or lists mixing objects with other values).
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use jason_rs::JasonBuilder;
+    /// let toml = JasonBuilder::new().jason_to_toml("Cargo.jason").unwrap();
+    /// println!("{}", toml);
+    /// ```
+    pub fn jason_to_toml(self, file_path: &str) -> CompilerResult<String> {
+        let lua = Rc::new(RefCell::new(LuaInstance::new_with_src(self.lua_src())?));
+        compile_jason_to_toml_from_file(file_path, lua)
+    }
+
+    /// Converts raw `.jason` source into a TOML document using the Lua dependencies included in the builder.
+    ///
+    /// # Arguments
+    /// * `src` - `.jason` source code as a string.
+    ///
+    /// # Errors
+    /// Returns an error if parsing fails or if the `out` value can't be expressed in TOML.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use jason_rs::JasonBuilder;
+    /// let src = r#"out {name: "alex", age: 20}"#;
+    /// let toml = JasonBuilder::new().jason_src_to_toml(src)?;
+    /// println!("{}", toml);
+    /// ```
+    pub fn jason_src_to_toml(self, src: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let lua = Rc::new(RefCell::new(LuaInstance::new_with_src(self.lua_src())?));
+        let toml = compile_jason_to_toml_from_src(src, lua)?;
+        Ok(toml)
+    }
+
+    /// Lexes, parses and evaluates a `.jason` file using the Lua dependencies included in the builder,
+    /// collecting every error instead of producing output.
+    ///
+    /// # Arguments
+    /// * `file_path` - Path to the `.jason` file.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use jason_rs::JasonBuilder;
+    /// let report = JasonBuilder::new().jason_check("Page.jason");
+    /// for err in &report.errors {
+    ///     eprintln!("{}", err);
+    /// }
+    /// ```
+    pub fn jason_check(self, file_path: &str) -> CheckReport {
+        let errors = match LuaInstance::new_with_src(self.lua_src()) {
+            Ok(lua) => check_jason_from_file(file_path, Rc::new(RefCell::new(lua))),
+            Err(e) => flatten_errors(e),
+        };
+        CheckReport { file: file_path.to_string(), errors }
+    }
+
+    /// Lexes, parses and evaluates raw `.jason` source using the Lua dependencies included in the builder,
+    /// collecting every error instead of producing output.
+    ///
+    /// # Arguments
+    /// * `src` - `.jason` source code as a string.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use jason_rs::JasonBuilder;
+    /// let report = JasonBuilder::new().jason_src_check(r#"out {name: "alex"}"#);
+    /// assert!(report.passed());
+    /// ```
+    pub fn jason_src_check(self, src: &str) -> CheckReport {
+        let errors = match LuaInstance::new_with_src(self.lua_src()) {
+            Ok(lua) => check_jason_from_src(src, Rc::new(RefCell::new(lua))),
+            Err(e) => flatten_errors(e),
+        };
+        CheckReport { file: "direct source".to_string(), errors }
+    }
+
+    /// Creates a [`JasonWatcher`] that recompiles `file_path` into `format` whenever it,
+    /// anything it imports or includes, or a Lua file included in the builder changes.
+    ///
+    /// # Arguments
+    /// * `file_path` - Path to the `.jason` file.
+    /// * `format` - Format the watcher renders each successful compile to.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use jason_rs::{JasonBuilder, OutputFormat};
+    /// let mut watcher = JasonBuilder::new()
+    ///     .include_lua_file("scripts/helpers.lua")?
+    ///     .watch("Page.jason", OutputFormat::Yaml);
+    /// ```
+    pub fn watch(self, file_path: &str, format: OutputFormat) -> JasonWatcher {
+        JasonWatcher::new(file_path, format, self.lua_sources)
+    }
 }
 
 /// Converts a `.jason` file into JSON using a default Lua environment.
@@ -147,3 +331,115 @@ pub fn jason_src_to_json(src: &str) -> Result<serde_json::Value, Box<dyn std::er
     Ok(json)
 }
 
+
+/// Converts a `.jason` file into a YAML document using a default Lua environment.
+///
+/// # Arguments
+/// * `file_path` - Path to the `.jason` file.
+///
+/// # Errors
+/// Returns an error if reading or parsing fails.
+///
+/// # Example
+/// ```rust,ignore
+/// use jason_rs::jason_to_yaml;
+/// let yaml = jason_to_yaml("Deployment.jason")?;
+/// println!("{}", yaml);
+/// ```
+pub fn jason_to_yaml(file_path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let json = jason_to_json(file_path)?;
+    Ok(value_to_yaml(&json))
+}
+
+/// Converts raw `.jason` source into a YAML document using a default Lua environment.
+///
+/// # Arguments
+/// * `src` - `.jason` source code as a string.
+///
+/// # Errors
+/// Returns an error if parsing fails.
+///
+/// # Example
+/// ```rust,ignore
+/// use jason_rs::jason_src_to_yaml;
+/// let src = r#"out {name: "alex", age: 20}"#;
+/// let yaml = jason_src_to_yaml(src).unwrap();
+/// println!("{}", yaml);
+/// ```
+pub fn jason_src_to_yaml(src: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let json = jason_src_to_json(src)?;
+    Ok(value_to_yaml(&json))
+}
+
+/// Converts a `.jason` file into a TOML document using a default Lua environment.
+///
+/// # Arguments
+/// * `file_path` - Path to the `.jason` file.
+///
+/// # Errors
+/// Returns an error if reading or parsing fails, or if the `out` value can't be expressed in TOML.
+///
+/// # Example
+/// ```rust,ignore
+/// use jason_rs::jason_to_toml;
+/// let toml = jason_to_toml("Cargo.jason")?;
+/// println!("{}", toml);
+/// ```
+pub fn jason_to_toml(file_path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let lua = Rc::new(RefCell::new(LuaInstance::new()?));
+    let toml = compile_jason_to_toml_from_file(file_path, lua)?;
+    Ok(toml)
+}
+
+/// Converts raw `.jason` source into a TOML document using a default Lua environment.
+///
+/// # Arguments
+/// * `src` - `.jason` source code as a string.
+///
+/// # Errors
+/// Returns an error if parsing fails or if the `out` value can't be expressed in TOML.
+///
+/// # Example
+/// ```rust,ignore
+/// use jason_rs::jason_src_to_toml;
+/// let src = r#"out {name: "alex", age: 20}"#;
+/// let toml = jason_src_to_toml(src).unwrap();
+/// println!("{}", toml);
+/// ```
+pub fn jason_src_to_toml(src: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let lua = Rc::new(RefCell::new(LuaInstance::new()?));
+    let toml = compile_jason_to_toml_from_src(src, lua)?;
+    Ok(toml)
+}
+
+/// Lexes, parses and evaluates a `.jason` file using a default Lua environment,
+/// collecting every error instead of producing output.
+///
+/// # Arguments
+/// * `file_path` - Path to the `.jason` file.
+///
+/// # Example
+/// ```rust,ignore
+/// use jason_rs::jason_check;
+/// let report = jason_check("Page.jason");
+/// println!("{}: {} errors", report.file, report.errors.len());
+/// ```
+pub fn jason_check(file_path: &str) -> CheckReport {
+    JasonBuilder::new().jason_check(file_path)
+}
+
+/// Lexes, parses and evaluates raw `.jason` source using a default Lua environment,
+/// collecting every error instead of producing output.
+///
+/// # Arguments
+/// * `src` - `.jason` source code as a string.
+///
+/// # Example
+/// ```rust,ignore
+/// use jason_rs::jason_src_check;
+/// let report = jason_src_check(r#"out {name: "alex"}"#);
+/// assert!(report.passed());
+/// ```
+pub fn jason_src_check(src: &str) -> CheckReport {
+    JasonBuilder::new().jason_src_check(src)
+}
@@ -31,6 +31,10 @@ pub enum JasonErrorKind {
     LuaFnError(String),
     LexerError(String),
     IndexError,
+    FormatError(String),
+    ArityError(String),
+    ArgumentError(String),
+    RecursionLimit(String),
 }
 
 pub struct JasonError {
@@ -135,6 +139,20 @@ impl JasonError {
         self
     }
     
+    /// Process exit code for this error, used by the `jason` binary.
+    ///
+    /// `3` for files that can't be read or imported, `4` when the output
+    /// can't be written in the requested format, `1` for everything else.
+    /// A bundle takes the code of its first error.
+    pub fn exit_code(&self) -> i32 {
+        match &self.kind {
+            JasonErrorKind::Bundle(errors) => errors.first().map_or(1, |e| e.exit_code()),
+            JasonErrorKind::FileError | JasonErrorKind::ImportError => 3,
+            JasonErrorKind::FormatError(_) => 4,
+            _ => 1,
+        }
+    }
+
     fn kind_str(&self) -> &str {
         match &self.kind {
             JasonErrorKind::ParseError(_) => "Parse Error",
@@ -162,6 +180,10 @@ impl JasonError {
             JasonErrorKind::LuaFnError(_) => "Lua Function Error",
             JasonErrorKind::IndexError => "Indexing Error",
             JasonErrorKind::IntervalError(_) => "IntervalError",
+            JasonErrorKind::FormatError(_) => "Format Error",
+            JasonErrorKind::ArityError(_) => "Arity Error",
+            JasonErrorKind::ArgumentError(_) => "Argument Error",
+            JasonErrorKind::RecursionLimit(_) => "Recursion Limit",
 
             JasonErrorKind::SyntaxErrorHere(_) => "SyntaxErrorHere",
         }
@@ -214,31 +236,35 @@ impl std::fmt::Display for JasonError {
                 }
             }
         }
-        // If we have an AST node, print the reconstructed code line
+        // If we have an AST node, write the reconstructed code line
         if let Some(node) = &self.node {
             let code_line = format!("{:>5} | {}", node.token.row, node.root().plain_sum.clone());
             match &self.kind {
                 JasonErrorKind::ImportError => {
-                    println!("{:>5}", highlight_string(&code_line, "*ALL*"));
+                    writeln!(f, "{:>5}", highlight_string(&code_line, "*ALL*"))?;
                 },
                 JasonErrorKind::TypeError(var)         |
                 JasonErrorKind::UndefinedVariable(var) |
                 JasonErrorKind::TemplateRescursion(var)|
                 JasonErrorKind::SyntaxErrorHere(var)   |
+                JasonErrorKind::FormatError(var)       |
+                JasonErrorKind::ArityError(var)        |
+                JasonErrorKind::ArgumentError(var)     |
+                JasonErrorKind::RecursionLimit(var)    |
                 JasonErrorKind::UndefinedTemplate(var) => {
-                    println!("{:>5}", highlight_string(&code_line, &var));
+                    writeln!(f, "{:>5}", highlight_string(&code_line, &var))?;
                 },
                 JasonErrorKind::LuaFnError(fn_name) => {
-                    println!("{:>5}", highlight_string(&code_line, &fn_name));
+                    writeln!(f, "{:>5}", highlight_string(&code_line, &fn_name))?;
                 },
                 JasonErrorKind::SyntaxError | JasonErrorKind::MissingKey | JasonErrorKind::MissingValue => { 
-                    println!("{:>5}", highlight_string(&code_line, "*ALL*"));
+                    writeln!(f, "{:>5}", highlight_string(&code_line, "*ALL*"))?;
                 },
 
                 JasonErrorKind::InvalidOperation(var) => {
-                    println!("{:>5}", highlight_string(&code_line, var));
+                    writeln!(f, "{:>5}", highlight_string(&code_line, var))?;
                 },
-                _ =>  println!("{:>5}", highlight_string(&code_line, "*ALL*"))
+                _ =>  writeln!(f, "{:>5}", highlight_string(&code_line, "*ALL*"))?
             }
         }
         
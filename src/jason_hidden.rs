@@ -4,9 +4,11 @@ use std::fs;
 use crate::jason_errors::{JasonError, JasonErrorKind};
 use crate::{context::Context, lexer, parser};
 use crate::lua_instance::LuaInstance;
+use crate::jason_toml::context_to_toml;
 use std::rc::Rc;
 use std::cell::RefCell;
-use crate::jason::CompilerResult;
+use crate::jason::{CompilerResult, OutputFormat};
+use crate::jason_yaml::value_to_yaml;
 
 pub fn jason_context_from_src(src: &str, lua: Rc<RefCell<LuaInstance>>) -> CompilerResult<Context> {
     let file_path: Rc<String> = Rc::new("direct source".to_string());
@@ -78,7 +80,7 @@ pub fn jason_context_from_file(file_path: String, lua: Rc<RefCell<LuaInstance>>)
 }
 
 
-pub fn jason_context_from_file_with_imports(file_path: String, lua: Rc<RefCell<LuaInstance>>, import_stack: Rc<RefCell<HashSet<String>>>) -> CompilerResult<Context> {
+pub fn jason_context_from_file_with_imports(file_path: String, lua: Rc<RefCell<LuaInstance>>, import_stack: Rc<RefCell<HashSet<String>>>, dependencies: Rc<RefCell<HashSet<String>>>) -> CompilerResult<Context> {
     let file_path: Rc<String> = Rc::new(file_path);
     
     // Check file existence
@@ -112,6 +114,7 @@ pub fn jason_context_from_file_with_imports(file_path: String, lua: Rc<RefCell<L
     };
     
     context.imported_from = import_stack.clone();
+    context.dependencies = dependencies.clone();
     
     let mut errors: Vec<JasonError> = Vec::new();
     for node in nodes.iter() {
@@ -136,3 +139,44 @@ pub fn compile_jason_from_src(src: &str, lua: Rc<RefCell<LuaInstance>>) -> Compi
     let context = jason_context_from_src(src, lua)?;
     Ok(context.out)
 }
+
+pub fn compile_jason_to_toml_from_file(file_path: &str, lua: Rc<RefCell<LuaInstance>>) -> CompilerResult<String> {
+    let context = jason_context_from_file(file_path.to_string(), lua)?;
+    context_to_toml(&context)
+}
+
+pub fn compile_jason_to_toml_from_src(src: &str, lua: Rc<RefCell<LuaInstance>>) -> CompilerResult<String> {
+    let context = jason_context_from_src(src, lua)?;
+    context_to_toml(&context)
+}
+
+// splits bundled errors back into the individual errors they were collected from
+pub fn flatten_errors(err: JasonError) -> Vec<JasonError> {
+    match err.kind {
+        JasonErrorKind::Bundle(errors) => errors.into_iter().flat_map(flatten_errors).collect(),
+        _ => vec![err],
+    }
+}
+
+pub fn check_jason_from_file(file_path: &str, lua: Rc<RefCell<LuaInstance>>) -> Vec<JasonError> {
+    match jason_context_from_file(file_path.to_string(), lua) {
+        Ok(_) => Vec::new(),
+        Err(e) => flatten_errors(e),
+    }
+}
+
+pub fn check_jason_from_src(src: &str, lua: Rc<RefCell<LuaInstance>>) -> Vec<JasonError> {
+    match jason_context_from_src(src, lua) {
+        Ok(_) => Vec::new(),
+        Err(e) => flatten_errors(e),
+    }
+}
+
+pub fn render_context(context: &Context, format: OutputFormat) -> CompilerResult<String> {
+    match format {
+        OutputFormat::Json => Ok(context.out.to_string()),
+        OutputFormat::PrettyJson => Ok(format!("{:#}", context.out)),
+        OutputFormat::Yaml => Ok(value_to_yaml(&context.out)),
+        OutputFormat::Toml => context_to_toml(context),
+    }
+}
@@ -0,0 +1,193 @@
+use crate::context::Context;
+use crate::jason_errors::{JasonError, JasonErrorKind, JasonResult};
+use serde_json::{Map, Value};
+
+/// Renders the `out` value of a compiled context as a TOML document.
+///
+/// Nested objects become `[tables]` and lists of objects become `[[arrays of tables]]`.
+/// Values TOML can't express (a top-level non-table, `null`, lists mixing objects with
+/// other values) are reported against the `out` statement instead of being dropped.
+pub fn context_to_toml(context: &Context) -> JasonResult<String> {
+    value_to_toml(&context.out).map_err(|msg| {
+        JasonError::new(
+            JasonErrorKind::FormatError("out".to_string()),
+            context.source_path.clone(),
+            context.out_node.clone(),
+            msg,
+        )
+    })
+}
+
+fn value_to_toml(value: &Value) -> Result<String, String> {
+    let map = match value {
+        Value::Object(map) => map,
+        other => {
+            return Err(format!(
+                "TOML documents must be a table, found {}",
+                value_kind(other)
+            ))
+        }
+    };
+
+    let mut out = String::new();
+    write_table(&mut out, &[], map)?;
+    Ok(out)
+}
+
+fn value_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a bool",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "a list",
+        Value::Object(_) => "an object",
+    }
+}
+
+fn is_table_array(values: &[Value]) -> Result<bool, String> {
+    let tables = values.iter().filter(|v| v.is_object()).count();
+    if tables == 0 {
+        return Ok(false);
+    }
+    if tables != values.len() {
+        return Err("mixes objects with other values".to_string());
+    }
+    Ok(true)
+}
+
+fn write_table(out: &mut String, path: &[String], map: &Map<String, Value>) -> Result<(), String> {
+    let mut tables = Vec::new();
+    let mut table_arrays = Vec::new();
+
+    // plain keys have to come before any sub-table header
+    for (key, value) in map {
+        let mut key_path = path.to_vec();
+        key_path.push(key.clone());
+
+        match value {
+            Value::Object(sub) => tables.push((key_path, sub)),
+            Value::Array(values) if is_table_array(values).map_err(|e| located(&key_path, &e))? => {
+                table_arrays.push((key_path, values))
+            }
+            _ => {
+                write_key(out, key);
+                out.push_str(" = ");
+                write_inline(out, &key_path, value)?;
+                out.push('\n');
+            }
+        }
+    }
+
+    for (key_path, sub) in tables {
+        // a header is only needed when the table holds values of its own
+        let has_values = sub.values().any(|v| match v {
+            Value::Object(_) => false,
+            Value::Array(values) => !matches!(is_table_array(values), Ok(true)),
+            _ => true,
+        });
+        if has_values || sub.is_empty() {
+            write_header(out, &key_path, false);
+        }
+        write_table(out, &key_path, sub)?;
+    }
+
+    for (key_path, values) in table_arrays {
+        for value in values {
+            if let Value::Object(sub) = value {
+                write_header(out, &key_path, true);
+                write_table(out, &key_path, sub)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_header(out: &mut String, path: &[String], array: bool) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(if array { "[[" } else { "[" });
+    for (i, key) in path.iter().enumerate() {
+        if i > 0 {
+            out.push('.');
+        }
+        write_key(out, key);
+    }
+    out.push_str(if array { "]]" } else { "]" });
+    out.push('\n');
+}
+
+fn write_inline(out: &mut String, path: &[String], value: &Value) -> Result<(), String> {
+    match value {
+        Value::Null => return Err(located(path, "is null, which TOML has no value for")),
+        Value::Bool(b) => out.push_str(&b.to_string()),
+        Value::Number(n) => out.push_str(&n.to_string()),
+        Value::String(s) => write_string(out, s),
+        Value::Array(values) => {
+            is_table_array(values).map_err(|e| located(path, &e))?;
+            out.push('[');
+            for (i, item) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                let mut item_path = path.to_vec();
+                item_path.push(i.to_string());
+                write_inline(out, &item_path, item)?;
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            if map.is_empty() {
+                out.push_str("{}");
+                return Ok(());
+            }
+            out.push_str("{ ");
+            for (i, (key, item)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                let mut key_path = path.to_vec();
+                key_path.push(key.clone());
+                write_key(out, key);
+                out.push_str(" = ");
+                write_inline(out, &key_path, item)?;
+            }
+            out.push_str(" }");
+        }
+    }
+    Ok(())
+}
+
+fn located(path: &[String], msg: &str) -> String {
+    format!("`{}` {}", path.join("."), msg)
+}
+
+fn write_key(out: &mut String, key: &str) {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        out.push_str(key);
+    } else {
+        write_string(out, key);
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
@@ -4,6 +4,7 @@ use serde_json::{Number, Value};
 use crate::astnode::ASTNode;
 use crate::jason_errors::{JasonError, JasonErrorKind, JasonResult};
 use crate::context::Context;
+use crate::template::Template;
 use crate::token::TokenType;
 
 //(a, b),[a, b), (a, b], [a, b]
@@ -209,6 +210,8 @@ pub enum JasonType {
     List(Box<JasonType>),
     Object(BTreeMap<String, JasonType>),
     Variance(Box<JasonType>),
+    // a template used as a value, only ever inferred
+    Template(String),
 }
 
 impl JasonType {
@@ -271,6 +274,12 @@ impl Context {
             TokenType::LessThan           |
             TokenType::GreaterThanEqualTo |
             TokenType::LessThanEqualTo  => { 
+                if node.left.as_ref().is_some_and(|left| left.token.token_type != TokenType::Empty) {
+                    return Err(self.err(
+                        JasonErrorKind::TypeError(node.token.plain()),
+                        format!("{} compares two values and can't be used as a type, intervals are written as {} n", node.plain_sum, node.token.plain())
+                    ));
+                }
                 let right = node.right.as_ref().ok_or_else(||
                     self.err(
                         JasonErrorKind::MissingValue,
@@ -585,6 +594,31 @@ impl Context {
         }
     }
 
+    /// Infers the type of `value` produced by `node`.
+    ///
+    /// Values reached through `?.` or `?[]` can be missing the next time around, so their
+    /// type also allows `Null`. A value that was missing this time could be anything.
+    pub fn infer_type_from_node(&mut self, node: &ASTNode, value: &serde_json::Value) -> JasonResult<JasonType> {
+        let infered_type = self.infer_type_from(value)?;
+        if !Self::is_nullable(node) {
+            return Ok(infered_type);
+        }
+        Ok(match infered_type {
+            JasonType::Null => JasonType::Any,
+            t => JasonType::Union(vec![Box::new(t), Box::new(JasonType::Null)]),
+        })
+    }
+
+    // whether `node` evaluates to null when optional data is missing
+    pub fn is_nullable(node: &ASTNode) -> bool {
+        match &node.token.token_type {
+            TokenType::QuestionDot | TokenType::OptionalIndex(_) => true,
+            TokenType::Dot | TokenType::Index(_) => node.left.as_ref().is_some_and(|left| Self::is_nullable(left)),
+            TokenType::DoubleQuestion => node.right.as_ref().is_some_and(|right| Self::is_nullable(right)),
+            _ => false,
+        }
+    }
+
     pub fn infer_type_from(&mut self, value: &serde_json::Value) -> JasonResult<JasonType> { 
         match value { 
             Value::String(_) => return Ok(JasonType::String),
@@ -624,6 +658,9 @@ impl Context {
                 }
             },
             Value::Object(mapping) => {
+                if let Some(name) = Template::referenced(value) {
+                    return Ok(JasonType::Template(name.to_string()));
+                }
                 Ok(
                     JasonType::Object(
                         mapping
@@ -657,6 +694,7 @@ impl JasonType {
             JasonType::Float => value.is_f64(),
             JasonType::Bool => value.is_boolean(),
             JasonType::Null => value.is_null(),
+            JasonType::Template(name) => Template::referenced(value) == Some(name.as_str()),
 
             JasonType::Variance(var_obj) => {
                 if let (Value::Object(obj), JasonType::Object(vobj) ) = (value, &**var_obj) {
@@ -850,6 +888,7 @@ impl fmt::Display for JasonType {
             }
 
             JasonType::Variance(value) => write!(f, "{}'", value),
+            JasonType::Template(name) => write!(f, "Template {}", name),
             JasonType::Union(types) => {
                 let mut first = true;
                 for t in types {
@@ -0,0 +1,123 @@
+use crate::jason::{CompilerResult, LuaSource, OutputFormat};
+use crate::jason_errors::{JasonError, JasonErrorKind};
+use crate::jason_hidden::{jason_context_from_file_with_imports, render_context};
+use crate::lua_instance::LuaInstance;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+/// Recompiles a `.jason` file whenever it, one of its imports or includes,
+/// or one of the builder's Lua files changes on disk.
+///
+/// Changes are found by polling modification times, so call [`JasonWatcher::poll`]
+/// on whatever interval suits you.
+///
+/// # Example
+/// ```rust,ignore
+/// use jason_rs::{JasonBuilder, OutputFormat};
+/// let mut watcher = JasonBuilder::new().watch("Page.jason", OutputFormat::PrettyJson);
+/// loop {
+///     match watcher.poll() {
+///         Some(Ok(json)) => println!("{}", json),
+///         Some(Err(err)) => eprintln!("{}", err),
+///         None => {}
+///     }
+///     std::thread::sleep(std::time::Duration::from_millis(500));
+/// }
+/// ```
+pub struct JasonWatcher {
+    file_path: String,
+    format: OutputFormat,
+    lua_sources: Vec<LuaSource>,
+    // None until the first compile, afterwards the last seen modification time of every watched file
+    watched: Option<HashMap<String, Option<SystemTime>>>,
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl JasonWatcher {
+    pub(crate) fn new(file_path: &str, format: OutputFormat, lua_sources: Vec<LuaSource>) -> Self {
+        JasonWatcher {
+            file_path: file_path.to_string(),
+            format,
+            lua_sources,
+            watched: None,
+        }
+    }
+
+    /// Compiles the file if this is the first poll or a watched file changed since the last one.
+    ///
+    /// Returns `None` when nothing changed.
+    pub fn poll(&mut self) -> Option<CompilerResult<String>> {
+        let changed = match &self.watched {
+            None => true,
+            Some(watched) => watched.iter().any(|(path, time)| modified(path) != *time),
+        };
+        if !changed {
+            return None;
+        }
+        Some(self.compile())
+    }
+
+    /// Files checked for changes, as of the last compile.
+    pub fn watched_files(&self) -> Vec<String> {
+        let mut files: Vec<String> = self
+            .watched
+            .as_ref()
+            .map(|watched| watched.keys().cloned().collect())
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+
+    fn compile(&mut self) -> CompilerResult<String> {
+        let dependencies = Rc::new(RefCell::new(HashSet::new()));
+
+        let mut watched_paths = vec![self.file_path.clone()];
+        for source in &self.lua_sources {
+            if let LuaSource::File { path, .. } = source {
+                watched_paths.push(path.clone());
+            }
+        }
+
+        let result = self.reload_lua().and_then(|lua_src| {
+            let lua = Rc::new(RefCell::new(LuaInstance::new_with_src(lua_src)?));
+            let import_stack = Rc::new(RefCell::new(HashSet::new()));
+            let context = jason_context_from_file_with_imports(self.file_path.clone(), lua, import_stack, dependencies.clone())?;
+            render_context(&context, self.format)
+        });
+
+        // dependencies are recorded before they're compiled, so a failing import is still watched
+        watched_paths.extend(dependencies.borrow().iter().cloned());
+        self.watched = Some(
+            watched_paths
+                .into_iter()
+                .map(|path| {
+                    let time = modified(&path);
+                    (path, time)
+                })
+                .collect(),
+        );
+
+        result
+    }
+
+    fn reload_lua(&mut self) -> CompilerResult<String> {
+        let mut lua_src = String::new();
+        for source in &mut self.lua_sources {
+            match source {
+                LuaSource::File { path, src } => {
+                    *src = std::fs::read_to_string(&*path).map_err(|_| {
+                        JasonError::new(JasonErrorKind::FileError, Rc::new(path.clone()), None, format!("failed to read from file {}", path))
+                    })?;
+                    lua_src.push_str(src);
+                }
+                LuaSource::Inline(src) => lua_src.push_str(src),
+            }
+        }
+        Ok(lua_src)
+    }
+}
@@ -0,0 +1,195 @@
+use serde_json::{Map, Value};
+
+/// Renders a compiled value as a YAML document.
+///
+/// Keys are written in the order the map iterates them, strings are only left
+/// unquoted when a YAML parser can't mistake them for another scalar type, and
+/// multi-line strings become literal blocks (`|`).
+pub fn value_to_yaml(value: &Value) -> String {
+    let mut out = String::new();
+    match value {
+        Value::Object(map) if !map.is_empty() => write_mapping(&mut out, map, 0),
+        Value::Array(values) if !values.is_empty() => write_sequence(&mut out, values, 0),
+        scalar => {
+            write_scalar(&mut out, scalar, 2);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn is_block(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => !map.is_empty(),
+        Value::Array(values) => !values.is_empty(),
+        _ => false,
+    }
+}
+
+fn write_mapping(out: &mut String, map: &Map<String, Value>, indent: usize) {
+    for (key, value) in map {
+        out.push_str(&" ".repeat(indent));
+        write_key(out, key);
+        out.push(':');
+        if is_block(value) {
+            out.push('\n');
+            write_block(out, value, indent + 2);
+        } else {
+            out.push(' ');
+            write_scalar(out, value, indent + 2);
+            out.push('\n');
+        }
+    }
+}
+
+fn write_sequence(out: &mut String, values: &[Value], indent: usize) {
+    for value in values {
+        if is_block(value) {
+            // render the item one level deeper, then swap its leading indentation for the dash
+            let mut item = String::new();
+            write_block(&mut item, value, indent + 2);
+            out.push_str(&" ".repeat(indent));
+            out.push_str("- ");
+            out.push_str(&item[indent + 2..]);
+        } else {
+            out.push_str(&" ".repeat(indent));
+            out.push_str("- ");
+            write_scalar(out, value, indent + 2);
+            out.push('\n');
+        }
+    }
+}
+
+fn write_block(out: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Object(map) => write_mapping(out, map, indent),
+        Value::Array(values) => write_sequence(out, values, indent),
+        scalar => {
+            out.push_str(&" ".repeat(indent));
+            write_scalar(out, scalar, indent);
+            out.push('\n');
+        }
+    }
+}
+
+fn write_key(out: &mut String, key: &str) {
+    if needs_quotes(key) || key.contains('\n') {
+        write_double_quoted(out, key);
+    } else {
+        out.push_str(key);
+    }
+}
+
+// `indent` is the indentation used for the lines of a literal block
+fn write_scalar(out: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(&b.to_string()),
+        Value::Number(n) => out.push_str(&n.to_string()),
+        Value::String(s) => write_string(out, s, indent),
+        Value::Array(_) => out.push_str("[]"),
+        Value::Object(_) => out.push_str("{}"),
+    }
+}
+
+fn write_string(out: &mut String, s: &str, indent: usize) {
+    // literal blocks can't start with indentation or hold other control characters,
+    // and one with only newlines in it reads back as an empty string
+    let block_safe = !s.trim_start_matches('\n').starts_with([' ', '\t'])
+        && !s.trim_end_matches('\n').is_empty()
+        && !s.chars().any(|c| is_special(c) && c != '\n' && c != '\t');
+    if s.contains('\n') && block_safe {
+        write_literal_block(out, s, indent);
+    } else if needs_quotes(s) {
+        write_double_quoted(out, s);
+    } else {
+        out.push_str(s);
+    }
+}
+
+fn write_literal_block(out: &mut String, s: &str, indent: usize) {
+    let body = s.trim_end_matches('\n');
+    let trailing = s.len() - body.len();
+
+    out.push('|');
+    match trailing {
+        0 => out.push('-'),
+        1 => {}
+        _ => out.push('+'),
+    }
+
+    for line in body.split('\n') {
+        out.push('\n');
+        if !line.is_empty() {
+            out.push_str(&" ".repeat(indent));
+            out.push_str(line);
+        }
+    }
+    for _ in 1..trailing {
+        out.push('\n');
+    }
+}
+
+fn write_double_quoted(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\u{2028}' => out.push_str("\\L"),
+            '\u{2029}' => out.push_str("\\P"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn needs_quotes(s: &str) -> bool {
+    let first = match s.chars().next() {
+        Some(c) => c,
+        None => return true,
+    };
+
+    // words YAML 1.1 and 1.2 parsers read as null or bool
+    let reserved = [
+        "null", "~", "true", "false", "yes", "no", "on", "off", "y", "n",
+    ];
+    if reserved.contains(&s.to_lowercase().as_str()) {
+        return true;
+    }
+    // the YAML 1.1 value and merge keys
+    if s == "=" || s == "<<" {
+        return true;
+    }
+
+    // anything number-like, dates and versions included, stays a string
+    let mut chars = s.chars();
+    chars.next();
+    let second_is_digit = chars.next().is_some_and(|c| c.is_ascii_digit());
+    if first.is_ascii_digit() || (matches!(first, '+' | '-' | '.') && second_is_digit) {
+        return true;
+    }
+    if matches!(s.to_lowercase().as_str(), ".inf" | "-.inf" | "+.inf" | ".nan") {
+        return true;
+    }
+
+    if "-?:,[]{}#&*!|>'\"%@`".contains(first) {
+        return true;
+    }
+
+    s.starts_with(char::is_whitespace)
+        || s.ends_with(char::is_whitespace)
+        || s.ends_with(':')
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.chars().any(is_special)
+}
+
+// control characters and the unicode line and paragraph separators, which YAML treats as line breaks
+fn is_special(c: char) -> bool {
+    c.is_control() || c == '\u{2028}' || c == '\u{2029}'
+}
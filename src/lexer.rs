@@ -1,5 +1,7 @@
 use crate::{jason_errors::{JasonError, JasonErrorKind}, token::{self, TokensToNode, Token, TokenType}};
 use crate::jason::CompilerResult;
+use crate::astnode::ASTNode;
+use crate::builtins;
 use std::rc::Rc;
 
 pub struct Lexer {
@@ -13,6 +15,20 @@ pub struct Lexer {
     colmn: usize,
 }
 
+// layout of a `"""` string, found by scanning it before it's lexed
+#[derive(Clone, Copy)]
+struct MultiLine {
+    // common indentation stripped from every line
+    indent: usize,
+    // the closing `"""` sits on its own line, so that line and the newline before it are dropped
+    trim_last_line: bool,
+}
+
+// a `"""` closes a multi-line string unless more quotes follow, then the last three close it
+fn closes_multiline(rest: &str) -> bool {
+    rest.starts_with("\"\"\"") && !rest[3..].starts_with('"')
+}
+
 impl Lexer {
     pub fn new_token(&self, token_type: TokenType, plain: String) -> Token {
         Token::new(token_type, plain, self.row, self.colmn)
@@ -56,12 +72,140 @@ impl Lexer {
     
     pub fn lex_string(&mut self) -> Token {
         self.next(); // skips the initial "
+        self.lex_string_body(None)
+    }
+
+    fn at_string_end(&self, multiline: Option<MultiLine>) -> bool {
+        match multiline {
+            Some(_) => closes_multiline(&self.contents[self.byte_index..]),
+            None => self.curr_char == '"',
+        }
+    }
+
+    // skips up to `indent` spaces or tabs at the start of a line in a multi-line string
+    fn skip_indent(&mut self, multiline: Option<MultiLine>) {
+        let indent = multiline.map_or(0, |m| m.indent);
+        for _ in 0..indent {
+            if self.curr_char != ' ' && self.curr_char != '\t' {
+                return;
+            }
+            self.next();
+        }
+    }
+
+    // leaves the lexer on the last " of the closing """ like every other token
+    fn finish_string(&mut self, multiline: Option<MultiLine>, last: &mut String) {
+        if let Some(multiline) = multiline {
+            self.next();
+            self.next();
+            if multiline.trim_last_line {
+                if let Some(i) = last.rfind('\n') {
+                    last.truncate(i);
+                }
+            }
+        }
+    }
+
+    /// Scans a `"""` string up front to find its indentation and moves onto its first line,
+    /// the current char is the first `"`.
+    fn lex_multiline_start(&mut self) -> Result<MultiLine, Token> {
+        let (row, colmn) = (self.row, self.colmn);
+        let rest = &self.contents[self.byte_index + 3..];
+
+        // find the closing """ the same way lexing will, escaped quotes don't close it
+        let mut chars = rest.char_indices();
+        let mut end = None;
+        while let Some((i, c)) = chars.next() {
+            if c == '\\' {
+                chars.next();
+            } else if closes_multiline(&rest[i..]) {
+                end = Some(i);
+                break;
+            }
+        }
+        let Some(end) = end else {
+            return Err(self.new_token(
+                TokenType::ERR(format!("UNABLE TO FIND ENDING \"\"\" for multi-line string at {} {}", row, colmn)),
+                "MULTI-LINE STRING LIT def ERROR no closing \"\"\"".to_string(),
+            ));
+        };
+
+        let lines: Vec<&str> = rest[..end].split('\n').collect();
+        let skip_first_line = lines.len() > 1 && lines[0].trim().is_empty();
+        let trim_last_line = lines.len() > 1 && lines[lines.len() - 1].trim().is_empty();
+        // the first line starts right after the quotes so it never counts towards the indentation
+        let indent = lines
+            .iter()
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+            .min()
+            .unwrap_or(0);
+        let multiline = MultiLine { indent, trim_last_line };
+
+        for _ in 0..3 {
+            self.next();
+        }
+        if skip_first_line {
+            while self.curr_char != '\n' {
+                self.next();
+            }
+            self.next();
+            self.skip_indent(Some(multiline));
+        }
+        Ok(multiline)
+    }
+
+    pub fn lex_multiline_string(&mut self) -> Token {
+        match self.lex_multiline_start() {
+            Ok(multiline) => self.lex_string_body(Some(multiline)),
+            Err(err) => err,
+        }
+    }
+
+    pub fn lex_multiline_composite_string(&mut self) -> Token {
+        match self.lex_multiline_start() {
+            Ok(multiline) => self.lex_composite_body(Some(multiline)),
+            Err(err) => err,
+        }
+    }
+
+    /// Lexes `r"..."` or `r#"..."#` where the current char is the `r`, the contents are kept as written.
+    /// Returns `None` when the `r` doesn't start a raw string.
+    fn lex_raw_string(&mut self) -> Option<Token> {
+        let (row, colmn) = (self.row, self.colmn);
+        let rest = &self.contents[self.byte_index + 1..];
+        let hashes = rest.len() - rest.trim_start_matches('#').len();
+        if !rest[hashes..].starts_with('"') {
+            return None;
+        }
+
+        let closing = format!("\"{}", "#".repeat(hashes));
+        let body = &rest[hashes + 1..];
+        let Some(end) = body.find(&closing) else {
+            return Some(self.new_token(
+                TokenType::ERR(format!("UNABLE TO FIND ENDING {} for raw string at {} {}", closing, row, colmn)),
+                "RAW STRING LIT def ERROR no closing \"".to_string(),
+            ));
+        };
+        let contents = body[..end].to_string();
+
+        // r, the hashes and quote, the contents, then onto the last char of the closing delimiter
+        let len = 1 + hashes + 1 + contents.chars().count() + closing.len() - 1;
+        for _ in 0..len {
+            self.next();
+        }
+        Some(self.new_token(TokenType::StringLiteral(contents.clone()), contents))
+    }
+
+    fn lex_string_body(&mut self, multiline: Option<MultiLine>) -> Token {
         let row = self.row;
         let colmn = self.colmn;
         
         let mut string_contents = String::new();
+        let mut escape_err = None;
         
-        while self.curr_char != '"' {
+        while !self.at_string_end(multiline) {
             if self.curr_char == '\0' {
                 return self.new_token(
                     TokenType::ERR(format!("UNABLE TO FIND ENDING \" for string at {} {}", row, colmn)), 
@@ -77,15 +221,25 @@ impl Lexer {
                         format!("STRING LIT def ERROR incomplete escape")
                     );
                 }
-                // Handle escape sequences
-                string_contents.push(self.curr_char);
-                self.next();
+                match self.lex_escape() {
+                    Ok(c) => string_contents.push(c),
+                    Err(err) => { escape_err.get_or_insert(err); },
+                }
                 continue;
             }
             
             // Push the Unicode character directly
             string_contents.push(self.curr_char);
             self.next();
+            if string_contents.ends_with('\n') {
+                self.skip_indent(multiline);
+            }
+        }
+        self.finish_string(multiline, &mut string_contents);
+
+        // the whole string is consumed first so lexing picks up after the closing "
+        if let Some(err) = escape_err {
+            return self.new_token(TokenType::ERR(err), "STRING LIT def ERROR invalid escape".to_string());
         }
         
         return self.new_token(
@@ -94,17 +248,89 @@ impl Lexer {
         );
     }
     
+    /// Decodes the escape sequence starting at the current char (the one after the `\\`)
+    /// and leaves the lexer on the char after it.
+    pub fn lex_escape(&mut self) -> Result<char, String> {
+        let (row, colmn) = (self.row, self.colmn);
+        let escaped = self.curr_char;
+        self.next();
+
+        let c = match escaped {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            '\\' | '"' | '\'' | '/' => escaped,
+            'u' if self.curr_char == '{' => {
+                self.next(); // skip '{'
+                let mut hex = String::new();
+                while self.curr_char != '}' {
+                    if !self.curr_char.is_ascii_hexdigit() || hex.len() == 6 {
+                        return Err(format!("invalid unicode escape \\u{{{}...}} at {} {}, expected 1 to 6 hex digits and a closing }}", hex, row, colmn));
+                    }
+                    hex.push(self.curr_char);
+                    self.next();
+                }
+                self.next(); // skip '}'
+                let code = u32::from_str_radix(&hex, 16)
+                    .map_err(|_| format!("invalid unicode escape \\u{{{}}} at {} {}", hex, row, colmn))?;
+                return char::from_u32(code)
+                    .ok_or_else(|| format!("\\u{{{}}} at {} {} is not a valid unicode character", hex, row, colmn));
+            },
+            'u' => {
+                let high = self.lex_hex4(row, colmn)?;
+                if !(0xD800..0xDC00).contains(&high) {
+                    return char::from_u32(high)
+                        .ok_or_else(|| format!("\\u{:04X} at {} {} is an unpaired surrogate", high, row, colmn));
+                }
+                // JSON style surrogate pair, the low half has to follow right away
+                if self.curr_char != '\\' || self.get_direct_next() != Some('u') {
+                    return Err(format!("\\u{:04X} at {} {} must be followed by a low surrogate \\uDC00-\\uDFFF", high, row, colmn));
+                }
+                self.next();
+                self.next();
+                let low = self.lex_hex4(row, colmn)?;
+                if !(0xDC00..0xE000).contains(&low) {
+                    return Err(format!("\\u{:04X} at {} {} must be followed by a low surrogate \\uDC00-\\uDFFF found \\u{:04X}", high, row, colmn, low));
+                }
+                let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                return char::from_u32(code)
+                    .ok_or_else(|| format!("invalid surrogate pair at {} {}", row, colmn));
+            },
+            other => return Err(format!("invalid escape sequence \\{} in string at {} {}", other, row, colmn)),
+        };
+        Ok(c)
+    }
+
+    fn lex_hex4(&mut self, row: usize, colmn: usize) -> Result<u32, String> {
+        let mut hex = String::new();
+        for _ in 0..4 {
+            if !self.curr_char.is_ascii_hexdigit() {
+                return Err(format!("invalid unicode escape \\u{} at {} {}, expected 4 hex digits", hex, row, colmn));
+            }
+            hex.push(self.curr_char);
+            self.next();
+        }
+        u32::from_str_radix(&hex, 16).map_err(|_| format!("invalid unicode escape \\u{} at {} {}", hex, row, colmn))
+    }
+
     pub fn lex_composite_string(&mut self) -> Token {
         self.next(); // skip opening "
-        
+        self.lex_composite_body(None)
+    }
+
+    fn lex_composite_body(&mut self, multiline: Option<MultiLine>) -> Token {
         let row = self.row;
         let colmn = self.colmn;
         
         let mut literals = Vec::new();
         let mut args = Vec::new();
         let mut current = String::new();
+        let mut escape_err = None;
         
-        while self.curr_char != '"' {
+        while !self.at_string_end(multiline) {
             if self.curr_char == '\0' {
                 return self.new_token(
                     TokenType::ERR(format!("UNABLE TO FIND ENDING \" for composite string at {} {}", row, colmn)),
@@ -121,8 +347,16 @@ impl Lexer {
                             "COMPOSITE STRING LIT def ERROR incomplete escape".to_string(),
                         );
                     }
-                    current.push(self.curr_char);
-                    self.next();
+                    // braces can be escaped so they aren't read as interpolation
+                    if self.curr_char == '{' || self.curr_char == '}' {
+                        current.push(self.curr_char);
+                        self.next();
+                        continue;
+                    }
+                    match self.lex_escape() {
+                        Ok(c) => current.push(c),
+                        Err(err) => { escape_err.get_or_insert(err); },
+                    }
                 }
                 '{' => {
                     literals.push(std::mem::take(&mut current));
@@ -172,11 +406,19 @@ impl Lexer {
                 _ => {
                     current.push(self.curr_char);
                     self.next();
+                    if current.ends_with('\n') {
+                        self.skip_indent(multiline);
+                    }
                 }
             }
         }
+        self.finish_string(multiline, &mut current);
         
         literals.push(current);
+
+        if let Some(err) = escape_err {
+            return self.new_token(TokenType::ERR(err), "COMPOSITE STRING LIT def ERROR invalid escape".to_string());
+        }
         
         let nodes = if args.is_empty() {
             Vec::new()
@@ -287,6 +529,71 @@ impl Lexer {
         );
     }
     
+    // whether the current char starts `word` as a whole word
+    fn at_word(&self, word: &str) -> bool {
+        let rest = &self.contents[self.byte_index..];
+        rest.starts_with(word) && !rest[word.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '_')
+    }
+
+    /// Lexes the `extends Parent(args) { ... }` part of `Child(params) extends Parent(args) { ... }`,
+    /// the current char is the `e` of `extends`.
+    fn lex_template_extends(&mut self, id: &Token, args: Vec<Vec<Token>>) -> Token {
+        for _ in 0.."extends".len() {
+            self.next();
+        }
+        self.skip_whitespace();
+
+        // the parent call and the child's block lex together like a template definition
+        let parent = self.lex();
+        let (parent_args, block) = match parent.token_type {
+            TokenType::TemplateDef(parent_args, block, None) => (parent_args, block),
+            TokenType::ERR(_) => return parent,
+            _ => {
+                let msg = format!("expected Parent(args) {{ ... }} after {}(...) extends at {} {}", id.plain(), parent.row, parent.colmn);
+                return self.new_token(TokenType::ERR(msg.clone()), msg);
+            }
+        };
+
+        let parent_call = ASTNode::new(Token::new(TokenType::FnCall(parent_args), parent.plain, parent.row, parent.colmn));
+        match args.to_nodes() {
+            Ok(nodes) => self.new_token(TokenType::TemplateDef(nodes, block, Some(Box::new(parent_call))), id.plain()),
+            Err(err) => self.new_token(TokenType::ERR(err.message.clone()), err.message),
+        }
+    }
+
+    // a `[...]` list, or an index when `indexing` is set
+    fn lex_list(&mut self, indexing: bool) -> Token {
+        let toks = match self.collect_toks_between(TokenType::OpenBracket, TokenType::ClosedBracket) {
+            Ok(toks) => toks,
+            Err(e) => return e
+        };
+        let args: Vec<Vec<Token>> = toks.split(|tok| tok.token_type == TokenType::Comma)
+            .map(|slice| slice.to_vec())
+            .collect();
+
+        match args.to_nodes() {
+            Ok(nodes) if indexing => self.new_token(TokenType::Index(nodes), String::new()),
+            Ok(nodes) => self.new_token(TokenType::List(nodes), "List".to_string()),
+            Err(err) => self.new_token(TokenType::ERR(err.message.clone()), err.message),
+        }
+    }
+
+    // `[` right after a value (`items[0]`, `grid[0][1]`, `f(x)[0]`, `"abc"[0]`) indexes it instead of starting a list
+    fn follows_value(&self) -> bool {
+        Self::ends_with_value(&self.contents[..self.byte_index])
+    }
+
+    fn ends_with_value(before: &str) -> bool {
+        match before.chars().last() {
+            Some(']') | Some(')') | Some('"') => true,
+            Some(c) if c.is_alphanumeric() || c == '_' => {
+                let word = &before[before.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_').len()..];
+                !TokenType::is_keyword(word) && !word.starts_with(|c: char| c.is_numeric())
+            },
+            _ => false,
+        }
+    }
+
     pub fn skip_whitespace(&mut self) {
         loop {
             match self.curr_char {
@@ -437,6 +744,9 @@ impl Lexer {
                 match self.get_direct_next() {
                     Some('"') => {
                         self.next();
+                        if closes_multiline(&self.contents[self.byte_index..]) {
+                            return self.lex_multiline_composite_string();
+                        }
                         let tok = self.lex_composite_string();
                         return tok; 
                     },
@@ -444,8 +754,20 @@ impl Lexer {
                 }
             },
             '&' => self.new_token(TokenType::Merge, format!("&")),
-            '"' => self.lex_string(),
-            '.' => self.new_token(TokenType::Dot, format!(".")),
+            '"' => {
+                if closes_multiline(&self.contents[self.byte_index..]) {
+                    return self.lex_multiline_string();
+                }
+                self.lex_string()
+            },
+            '.' => {
+                if self.contents[self.byte_index..].starts_with("...") {
+                    self.next();
+                    self.next();
+                    return self.new_token(TokenType::Spread, "...".to_string());
+                }
+                self.new_token(TokenType::Dot, format!("."))
+            },
             ',' => self.new_token(TokenType::Comma, format!(",")),
             ':' => {
                 if let Some(next) = self.get_direct_next() {
@@ -496,19 +818,28 @@ impl Lexer {
                 return self.new_token(TokenType::OpenParen, format!("("));
             },
             '[' => {
-                let toks = match self.collect_toks_between(TokenType::OpenBracket, TokenType::ClosedBracket) {
-                    Ok(toks) => toks,
-                    Err(e) => return e
-                };
-                let args: Vec<Vec<Token>> = toks.split(|tok| tok.token_type == TokenType::Comma)
-                    .map(|slice| slice.to_vec())
-                    .collect();
-
-                match args.to_nodes() {
-                    Ok(nodes) => return self.new_token(TokenType::List(nodes), format!("List")),
-                    Err(err) => return self.new_token(TokenType::ERR(err.message.clone()), err.message),
+                let indexing = self.follows_value();
+                self.lex_list(indexing)
+            },
+            '?' => {
+                match self.get_direct_next() {
+                    Some('.') => {
+                        self.next();
+                        self.new_token(TokenType::QuestionDot, "?.".to_string())
+                    },
+                    Some('?') => {
+                        self.next();
+                        self.new_token(TokenType::DoubleQuestion, "??".to_string())
+                    },
+                    Some('[') => {
+                        self.next();
+                        match self.lex_list(true) {
+                            Token { token_type: TokenType::Index(nodes), .. } => self.new_token(TokenType::OptionalIndex(nodes), String::new()),
+                            err => err,
+                        }
+                    },
+                    _ => self.new_token(TokenType::Unknown('?'), "?".to_string()),
                 }
-
             },
 
             '\'' => return self.new_token(TokenType::VarianceOperator, format!("'")),
@@ -566,15 +897,39 @@ impl Lexer {
             ')' => return self.new_token(TokenType::ClosedParen, format!(")")),
             ']' => return self.new_token(TokenType::ClosedBracket, format!("]")),
             '}' => return self.new_token(TokenType::ClosedCurly, format!("}}")),
-            '=' => return self.new_token(TokenType::Equals, format!("=")),
+            '=' => {
+                if self.get_direct_next() == Some('=') {
+                    self.next();
+                    return self.new_token(TokenType::DoubleEquals, "==".to_string());
+                }
+                return self.new_token(TokenType::Equals, format!("="))
+            },
+            '!' => {
+                if self.get_direct_next() == Some('=') {
+                    self.next();
+                    return self.new_token(TokenType::NotEquals, "!=".to_string());
+                }
+                self.new_token(TokenType::Unknown('!'), "!".to_string())
+            },
             '\0' => self.new_token(TokenType::EOF, format!("\\0")),
             c => { 
+                if c == 'r' {
+                    if let Some(tok) = self.lex_raw_string() {
+                        return tok;
+                    }
+                }
                 if c.is_alphabetic() || c == '_' {
+                    let start_byte = self.byte_index;
                     let id = self.lex_id();
                     self.skip_whitespace();
                     
                     if TokenType::is_keyword(&id.plain()) {
+                        // `{if: 1}`, a keyword right before a `:` is a key
+                        let is_key = self.curr_char == ':' && !matches!(self.get_direct_next(), Some(':') | Some('='));
                         self.back();
+                        if is_key {
+                            return Token { token_type: TokenType::ID, ..id };
+                        }
                         return id;
                     }
 
@@ -590,20 +945,35 @@ impl Lexer {
                             self.next();
                             self.skip_whitespace();
                             
-                            if Token::is_fn_keyword(&id.plain) {
+                            // `f(x) != y` is a comparison, not a lua call
+                            // checked first so lua functions can share a name with a builtin, like `max(a, b)!`
+                            if self.curr_char == '!' && self.get_direct_next() != Some('=') {
+                                match args.to_nodes() {
+                                    Ok(nodes) => return self.new_token(TokenType::LuaFnCall(nodes), format!("{}", id.plain())), 
+                                    Err(err) => return self.new_token(TokenType::ERR(err.message.clone()), err.message),
+                                }
+
+                            }
+
+                            // builtins can be redefined, so `len(x) => ...` and `values(a) { ... }` are still definitions
+                            let defines = (self.curr_char == '=' && self.get_direct_next() == Some('>'))
+                                || self.at_word("extends") || self.curr_char == '{';
+                            if Token::is_fn_keyword(&id.plain) && !(builtins::is_builtin(&id.plain) && defines) {
                                 self.back();
                                 match args.to_nodes() {
                                     Ok(nodes) => return self.new_token(TokenType::FnCall(nodes), format!("{}", id.plain())).find_fn_keyword(), 
                                     Err(err) => return self.new_token(TokenType::ERR(err.message.clone()), err.message),
                                 }
                             }
-
-                            if self.curr_char == '!' {
+                            if self.curr_char == '=' && self.get_direct_next() == Some('>') {
+                                self.next();
                                 match args.to_nodes() {
-                                    Ok(nodes) => return self.new_token(TokenType::LuaFnCall(nodes), format!("{}", id.plain())), 
+                                    Ok(nodes) => return self.new_token(TokenType::FnDef(nodes), id.plain()),
                                     Err(err) => return self.new_token(TokenType::ERR(err.message.clone()), err.message),
                                 }
-
+                            }
+                            if self.at_word("extends") {
+                                return self.lex_template_extends(&id, args);
                             }
                             if self.curr_char == '{' {
                                 let inner_toks: Vec<Token> = match self.collect_toks_between(TokenType::OpenCurly, TokenType::ClosedCurly) {
@@ -616,7 +986,7 @@ impl Lexer {
                                 inner_args.retain(|vec| !vec.is_empty());
 
                                 match (args.to_nodes(), inner_args.to_nodes()) {
-                                    (Ok(nodes), Ok(inner_nodes)) => return self.new_token(TokenType::TemplateDef(nodes, inner_nodes), format!("{}", id.plain())),
+                                    (Ok(nodes), Ok(inner_nodes)) => return self.new_token(TokenType::TemplateDef(nodes, inner_nodes, None), format!("{}", id.plain())),
                                     (Ok(_), Err(err)) |
                                     (Err(err), Ok(_)) => return self.new_token(TokenType::ERR(err.message.clone()), err.message),
 
@@ -634,6 +1004,21 @@ impl Lexer {
                         },
                         _ => {}
                     }
+                    // `list map Template` and `list sort` on one line, so these words are still names anywhere else
+                    if Self::ends_with_value(self.contents[..start_byte].trim_end_matches([' ', '\t'])) {
+                        let operator = match id.plain().as_str() {
+                            "map" => Some(TokenType::Map(Vec::new())),
+                            "sort" => Some(TokenType::Sort),
+                            "unique" => Some(TokenType::Unique),
+                            "reverse" => Some(TokenType::Reverse),
+                            "group" => Some(TokenType::Group),
+                            _ => None,
+                        };
+                        if let Some(operator) = operator {
+                            self.back();
+                            return Token { token_type: operator, ..id };
+                        }
+                    }
                     self.back();
                     return id;
                 }
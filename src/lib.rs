@@ -6,8 +6,15 @@ mod token;
 mod parser;
 mod context;
 mod template;
+mod function;
+mod builtins;
 mod astnode;
 mod lua_instance;
 mod jason_errors;
 mod jason_types;
+mod jason_yaml;
+mod jason_toml;
+mod jason_watch;
 pub use jason::*;
+pub use jason_errors::{JasonError, JasonErrorKind};
+pub use jason_watch::JasonWatcher;
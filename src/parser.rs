@@ -32,6 +32,26 @@ impl Parser {
         return true;
     }
     
+    // consumes the keyword `expected`, `owner` is the construct it belongs to
+    fn expect_keyword(&mut self, expected: TokenType, owner: &Token) -> JasonResult<Token> {
+        let keyword = Token::new(expected.clone(), String::new(), 0, 0).pretty();
+        match self.current().cloned() {
+            Some(tok) if tok.token_type == expected => {
+                self.next();
+                Ok(tok)
+            },
+            found => {
+                let found = found.map_or_else(|| "end of input".to_string(), |t| t.pretty());
+                Err(JasonError::new(
+                    JasonErrorKind::ParseError(owner.plain()),
+                    self.file_path.clone(),
+                    None,
+                    format!("expected `{}` in {} expression on line {} but found {}", keyword, owner.plain(), owner.row, found)
+                ))
+            }
+        }
+    }
+
     fn factor(&mut self) -> JasonResult<ASTNode> {
         let token = self.current().cloned().unwrap_or(Token::new(TokenType::EOT, "EOT".to_string(), 1, 1));
         
@@ -48,6 +68,7 @@ impl Parser {
             TokenType::NullType             | 
             TokenType::List(_)              | 
             TokenType::FnCall(_)            |
+            TokenType::Builtin(_)           |
             TokenType::LuaFnCall(_)         |
             TokenType::Import(_)            |
             TokenType::StringLiteral(_)     | 
@@ -84,6 +105,76 @@ impl Parser {
                     ) 
                 ) 
             },
+            TokenType::If => {
+                self.next(); // consume 'if'
+                let condition = self.coalesce()?;
+                self.expect_keyword(TokenType::Then, &token)?;
+                let then_branch = self.coalesce()?;
+                let else_token = self.expect_keyword(TokenType::Else, &token)?;
+                let else_branch = self.coalesce()?;
+
+                let plain_sum = format!("if {} then {} else {}", condition.plain_sum, then_branch.plain_sum, else_branch.plain_sum);
+                let branches = ASTNode::new(else_token)
+                    .children(Some(Box::new(then_branch)), Some(Box::new(else_branch)));
+                let mut node = ASTNode::new(token)
+                    .children(Some(Box::new(condition)), Some(Box::new(branches)));
+                node.plain_sum = plain_sum;
+                Ok(node)
+            },
+            TokenType::Spread => {
+                self.next(); // consume '...'
+                let right = self.postfix()?;
+                let plain_sum = format!("...{}", right.plain_sum);
+                let mut node = ASTNode::new(token).children(None, Some(Box::new(right)));
+                node.plain_sum = plain_sum;
+                Ok(node)
+            },
+            TokenType::Let => {
+                self.next(); // consume 'let'
+                let name = match self.current().cloned() {
+                    Some(name) if name.token_type == TokenType::ID => name,
+                    found => {
+                        let found = found.map_or_else(|| "end of input".to_string(), |t| t.pretty());
+                        return Err(JasonError::new(
+                            JasonErrorKind::ParseError(token.plain()),
+                            self.file_path.clone(),
+                            None,
+                            format!("expected a name after `let` on line {} but found {}", token.row, found)
+                        ));
+                    }
+                };
+                self.next();
+                let equals = self.expect_keyword(TokenType::Equals, &token)?;
+                let value = self.coalesce()?;
+                let binding = ASTNode::new(equals)
+                    .children(Some(Box::new(ASTNode::new(name))), Some(Box::new(value)));
+
+                // without `in` the binding is for the rest of the block it's in
+                let body = match self.current() {
+                    Some(tok) if tok.token_type == TokenType::In => {
+                        self.next();
+                        Some(self.coalesce()?)
+                    },
+                    _ => None,
+                };
+
+                let mut node = ASTNode::new(token);
+                node.plain_sum = match &body {
+                    Some(body) => format!("let {} in {}", binding.plain_sum, body.plain_sum),
+                    None => format!("let {}", binding.plain_sum),
+                };
+                node.left = Some(Box::new(binding));
+                node.right = body.map(Box::new);
+                Ok(node)
+            },
+            TokenType::FnDef(_) => {
+                self.next(); // consume `name(params) =>`
+                let body = self.coalesce()?;
+                let plain_sum = format!("{} {}", token.pretty(), body.plain_sum);
+                let mut node = ASTNode::new(token).children(None, Some(Box::new(body)));
+                node.plain_sum = plain_sum;
+                Ok(node)
+            },
             TokenType::Out | TokenType::Include | TokenType::Info | TokenType::InfoT => {
                 self.next(); // consume the keyword
                 let rhs = self.expr()?; // Parse what comes after
@@ -135,6 +226,45 @@ impl Parser {
         }
     }
     
+    // `a.b`, `a[0]` and chains of them bind tighter than any operator
+    fn postfix(&mut self) -> JasonResult<ASTNode> {
+        let mut node = self.factor()?;
+
+        while let Some(token) = self.current().cloned() {
+            match token.token_type {
+                TokenType::Dot | TokenType::QuestionDot => {
+                    self.next();
+                    // keywords are fine as keys, `config.type` reads better than `config at "type"`
+                    let key = match self.current().cloned() {
+                        Some(key) if key.token_type == TokenType::ID || TokenType::is_keyword(&key.plain) => key,
+                        found => {
+                            let found = found.map_or_else(|| "end of input".to_string(), |t| t.pretty());
+                            return Err(JasonError::new(
+                                JasonErrorKind::ParseError(format!("{}{}", node.plain_sum, token.plain())),
+                                self.file_path.clone(),
+                                None,
+                                format!("expected a key after `{}{}` on line {} but found {}", node.plain_sum, token.plain(), token.row, found)
+                            ));
+                        }
+                    };
+                    self.next();
+                    let plain_sum = format!("{}{}{}", node.plain_sum, token.plain(), key.plain());
+                    node = ASTNode::new(token)
+                        .children(Some(Box::new(node)), Some(Box::new(ASTNode::new(key))));
+                    node.plain_sum = plain_sum;
+                },
+                TokenType::Index(_) | TokenType::OptionalIndex(_) => {
+                    self.next();
+                    let plain_sum = format!("{}{}", node.plain_sum, token.pretty());
+                    node = ASTNode::new(token).children(Some(Box::new(node)), None);
+                    node.plain_sum = plain_sum;
+                },
+                _ => break,
+            }
+        }
+        Ok(node)
+    }
+    
     fn term(&mut self) -> JasonResult<ASTNode> {
 
         if let Some(token) = self.current().cloned() {
@@ -144,7 +274,7 @@ impl Parser {
                 TokenType::GreaterThanEqualTo |
                 TokenType::LessThanEqualTo => {
                     self.next();
-                    let right = self.factor()?;
+                    let right = self.postfix()?;
                     return Ok(ASTNode::new(token).children(None, Some(Box::new(right))));
                 },
                 _ => {}
@@ -152,7 +282,7 @@ impl Parser {
         }
 
 
-        let mut node = self.factor()?;
+        let mut node = self.postfix()?;
 
 
         while let Some(token) = self.current().cloned() {
@@ -166,27 +296,62 @@ impl Parser {
 
                 TokenType::Repeat | TokenType::Mult | TokenType::Divide | TokenType::Mod => {
                     self.next();
-                    let right = self.factor()?;
+                    let right = self.postfix()?;
                     node = ASTNode::new(token)
                         .children(Some(Box::new(node)), Some(Box::new(right)));
                 },
                 TokenType::At          | 
                 TokenType::Pick        | 
                 TokenType::UPick       |
-                TokenType::With        |
-                TokenType::Map(_)   => {
+                TokenType::With        => {
                     self.next();
                     let right = self.addition()?;
                     node = ASTNode::new(token)
                         .children(Some(Box::new(node)), Some(Box::new(right)));
                 },
-                TokenType::GreaterThan |
-                TokenType::LessThan    |
-                TokenType::GreaterThanEqualTo|
-                TokenType::LessThanEqualTo   => {
-                    self.next(); // consume 'out'
+                TokenType::Map(_) | TokenType::Filter(_) => {
+                    self.next();
+                    let right = self.coalesce()?;
                     node = ASTNode::new(token)
-                        .children(None, Some(Box::new(self.factor()?)));
+                        .children(Some(Box::new(node)), Some(Box::new(right)));
+                },
+                TokenType::Unique | TokenType::Reverse => {
+                    self.next();
+                    node = ASTNode::new(token)
+                        .children(Some(Box::new(node)), None);
+                },
+                TokenType::Sort | TokenType::Group => {
+                    self.next();
+                    // `sort` can go without a key, `group` can't
+                    let by = match self.current().cloned() {
+                        Some(by) if matches!(by.token_type, TokenType::By(_)) => {
+                            self.next();
+                            let key = self.coalesce()?;
+                            Some(Box::new(ASTNode::new(by).children(None, Some(Box::new(key)))))
+                        },
+                        _ if token.token_type == TokenType::Group => {
+                            return Err(JasonError::new(
+                                JasonErrorKind::ParseError(token.plain()),
+                                self.file_path.clone(),
+                                None,
+                                format!("expected `by(item) key` after group on line {}", token.row)
+                            ));
+                        },
+                        _ => None,
+                    };
+                    node = ASTNode::new(token)
+                        .children(Some(Box::new(node)), by);
+                },
+                TokenType::Reduce(_) => {
+                    self.next();
+                    let body = self.coalesce()?;
+                    let from_token = self.expect_keyword(TokenType::From, &token)?;
+                    let init = self.coalesce()?;
+                    // same shape as `if`, the body and initial value hang off the `from`
+                    let right = ASTNode::new(from_token)
+                        .children(Some(Box::new(body)), Some(Box::new(init)));
+                    node = ASTNode::new(token)
+                        .children(Some(Box::new(node)), Some(Box::new(right)));
                 },
                 _ => break,
             }
@@ -196,14 +361,14 @@ impl Parser {
     
     fn expr(&mut self) -> JasonResult<ASTNode> {
         // Fallback: normal expressions
-        let mut node = self.addition()?;
+        let mut node = self.coalesce()?;
         while let Some(token) = self.current().cloned() {
             match token.token_type {
                 TokenType::Colon | TokenType::From | TokenType::AS | TokenType::Append |
                 TokenType::Equals | TokenType::DoubleColon | TokenType::Narwhal |
                 TokenType::SpiderWalrus => {
                     self.next();
-                    let right = self.addition()?;
+                    let right = self.coalesce()?;
                     node = ASTNode::new(token)
                         .children(Some(Box::new(node)), Some(Box::new(right)));
                 },
@@ -214,6 +379,94 @@ impl Parser {
     }
 
     
+    // `a ?? b` binds loosest so `cfg?.port ?? base + 1` defaults to the whole sum
+    fn coalesce(&mut self) -> JasonResult<ASTNode> {
+        let mut node = self.logic_or()?;
+
+        while let Some(token) = self.current().cloned() {
+            match token.token_type {
+                TokenType::DoubleQuestion => {
+                    self.next();
+                    let right = self.logic_or()?;
+                    node = ASTNode::new(token)
+                        .children(Some(Box::new(node)), Some(Box::new(right)));
+                },
+                _ => break,
+            }
+        }
+        Ok(node)
+    }
+
+    fn logic_or(&mut self) -> JasonResult<ASTNode> {
+        let mut node = self.logic_and()?;
+
+        while let Some(token) = self.current().cloned() {
+            match token.token_type {
+                TokenType::Or => {
+                    self.next();
+                    let right = self.logic_and()?;
+                    node = ASTNode::new(token)
+                        .children(Some(Box::new(node)), Some(Box::new(right)));
+                },
+                _ => break,
+            }
+        }
+        Ok(node)
+    }
+
+    fn logic_and(&mut self) -> JasonResult<ASTNode> {
+        let mut node = self.logic_not()?;
+
+        while let Some(token) = self.current().cloned() {
+            match token.token_type {
+                TokenType::And => {
+                    self.next();
+                    let right = self.logic_not()?;
+                    node = ASTNode::new(token)
+                        .children(Some(Box::new(node)), Some(Box::new(right)));
+                },
+                _ => break,
+            }
+        }
+        Ok(node)
+    }
+
+    fn logic_not(&mut self) -> JasonResult<ASTNode> {
+        if let Some(token) = self.current().cloned() {
+            if token.token_type == TokenType::Not {
+                self.next();
+                let right = self.logic_not()?;
+                let mut node = ASTNode::new(token).children(None, Some(Box::new(right)));
+                node.plain_sum = node.plain_sum.trim_start().to_string();
+                return Ok(node);
+            }
+        }
+        self.comparison()
+    }
+
+    // a comparator at the start of a term is an interval type (`>= 0`), after a value it compares
+    fn comparison(&mut self) -> JasonResult<ASTNode> {
+        let mut node = self.addition()?;
+
+        while let Some(token) = self.current().cloned() {
+            match token.token_type {
+                TokenType::GreaterThan        |
+                TokenType::LessThan           |
+                TokenType::GreaterThanEqualTo |
+                TokenType::LessThanEqualTo    |
+                TokenType::DoubleEquals       |
+                TokenType::NotEquals          => {
+                    self.next();
+                    let right = self.addition()?;
+                    node = ASTNode::new(token)
+                        .children(Some(Box::new(node)), Some(Box::new(right)));
+                },
+                _ => break,
+            }
+        }
+        Ok(node)
+    }
+
     fn addition(&mut self) -> JasonResult<ASTNode> {
         let mut node = self.term()?;
         
@@ -1,5 +1,6 @@
 use crate::astnode::ASTNode;
-use crate::context::Context;
+use crate::context::{Context, Scope};
+use crate::function::MAX_CALL_DEPTH;
 use crate::jason_errors::JasonError;
 use crate::jason_errors::JasonErrorKind;
 use crate::jason_errors::JasonResult;
@@ -8,110 +9,184 @@ use crate::token;
 use crate::token::Token;
 use crate::jason_errors;
 use crate::token::TokenType;
-use std::collections::HashMap;
+use std::collections::HashSet;
 use std::rc::Rc;
+use std::sync::OnceLock;
+
+// templates used as values are stored as `{"$template:<nonce>": name}`, the nonce is picked once per
+// process so objects built from data (`from_entries`, lua, included files) can't pose as a template
+fn template_key() -> &'static str {
+    static KEY: OnceLock<String> = OnceLock::new();
+    KEY.get_or_init(|| format!("$template:{:016x}", rand::random::<u64>()))
+}
+
+#[derive(Debug, Clone)]
+pub struct TemplateParam {
+    name: String,
+    // evaluated at the call site when the argument isn't passed
+    default: Option<ASTNode>,
+    // `...name` collects the arguments left over as a list
+    rest: bool,
+}
 
 #[derive(Debug, Clone)]
 pub struct Template {
     name: String,
-    arguments: Vec<String>,
+    arguments: Vec<TemplateParam>,
     block: token::Args,
-    typing: Option<(Vec<JasonType>, JasonType)>
+    typing: Option<(Vec<JasonType>, JasonType)>,
+    // `Parent(args)` call from `extends`, its args are evaluated with this template's parameters bound
+    parent: Option<Box<ASTNode>>,
 }
 
 impl Template { 
-    pub fn new(context: &Context, name: String, arguments: token::Args, block: token::Args, typing: Option<(Vec<JasonType>, JasonType)>) -> JasonResult<Self> {
+    pub fn new(context: &Context, name: String, arguments: token::Args, block: token::Args, typing: Option<(Vec<JasonType>, JasonType)>, parent: Option<Box<ASTNode>>) -> JasonResult<Self> {
         
         for node in &block {
             Self::check_self_reference(&context, &node, &node, &name)?;
         }
+        if let Some(parent) = &parent {
+            Self::check_self_reference(context, parent, parent, &name)?;
+        }
         
-        let args: Vec<(String, bool, usize, usize)> = arguments
-            .into_iter()
-            .map(|n|
-                match &n.token.token_type {
-                    TokenType::Auto(id) => {
-                        Ok((id.clone(), true, n.token.row, n.token.colmn))
-                    },
-                    TokenType::ID       => {
-                        Ok((n.token.plain(), false, n.token.row, n.token.colmn))
-                    },
-                    _ => {
-                        Err(
-                            JasonError::new(JasonErrorKind::SyntaxErrorHere(n.plain_sum.clone()), context.source_path.clone(), Some(Rc::new(n.clone())), format!("expected either an ID or *ID in Template Def parameters"))
-                        )
-                    }
+        let mut args: Vec<(TemplateParam, bool, usize, usize)> = Vec::new();
+        for n in arguments {
+            // `name = default`, `*name = default` and `...name`
+            let (param, default, rest) = match &n.token.token_type {
+                TokenType::Equals => (
+                    n.left.as_deref().cloned().unwrap_or_else(ASTNode::empty),
+                    n.right.as_deref().cloned(),
+                    false,
+                ),
+                TokenType::Spread => (n.right.as_deref().cloned().unwrap_or_else(ASTNode::empty), None, true),
+                _ => (n.clone(), None, false),
+            };
+
+            let (param_name, fill) = match &param.token.token_type {
+                TokenType::Auto(id) => (id.clone(), true),
+                TokenType::ID       => (param.token.plain(), false),
+                _ => {
+                    return Err(
+                        JasonError::new(JasonErrorKind::SyntaxErrorHere(n.plain_sum.clone()), context.source_path.clone(), Some(Rc::new(n.clone())), "expected either an ID, *ID, ...ID or ID = default in Template Def parameters".to_string())
+                    )
                 }
-            )
-            .collect::<JasonResult<Vec<(String, bool, usize, usize)>>>()
-            .map_err(|e| e)?;
+            };
+
+            if let Some((last, _, _, _)) = args.last().filter(|(p, _, _, _)| p.rest) {
+                return Err(
+                    JasonError::new(JasonErrorKind::SyntaxErrorHere(n.plain_sum.clone()), context.source_path.clone(), Some(Rc::new(n.clone())), format!("...{} has to be the last parameter of template {}", last.name, name))
+                )
+            }
+
+            if let Some(default) = &default {
+                Self::check_self_reference(context, &n, default, &name)?;
+            } else if !rest && args.iter().any(|(p, _, _, _)| p.default.is_some()) {
+                return Err(
+                    JasonError::new(JasonErrorKind::SyntaxErrorHere(n.plain_sum.clone()), context.source_path.clone(), Some(Rc::new(n.clone())), format!("parameter {} of template {} needs a default because it comes after a parameter with one", param_name, name))
+                )
+            }
+
+            args.push((TemplateParam { name: param_name, default, rest }, fill, n.token.row, n.token.colmn));
+        }
         
         let mut block = block;
 
-        for (key, fill, row, colmn) in &args {
+        for (param, fill, row, colmn) in &args {
             if *fill {
-                block.push(Template::build_auto_field(key.clone(), *row, *colmn))
+                block.push(Template::build_auto_field(param.name.clone(), *row, *colmn))
             }
         }
 
-        Ok(Self { name, arguments: args.into_iter().map(|t| t.0).collect(), block, typing })
+        Ok(Self { name, arguments: args.into_iter().map(|t| t.0).collect(), block, typing, parent })
     }
-    
-    pub fn resolve(&self, context: &mut Context, arguments: &token::Args) -> jason_errors::JasonResult<Option<serde_json::Value>> {
-        // Parse arguments into proper AST nodes first 
-        
-        // Build map of parameter name -> argument value
-        let args: Vec<(String, serde_json::Value)> = self
-            .arguments
-            .iter()
-            .cloned()
-            .zip(
-               arguments 
-                    .iter()
-                    .map(|node| context.to_json(node))
-                    .collect::<jason_errors::JasonResult<Vec<_>>>()?
-            )
-            .filter_map(|(k, v)| v.map(|val| (k, val)))
-            .collect();
-        /*
-        let mut fill_in_args: Vec<String> = Vec::new();
 
-        let mut args: Vec<(String, serde_json::Value)>;
-        
+    pub fn resolve(&self, context: &mut Context, arguments: &token::Args) -> jason_errors::JasonResult<Option<serde_json::Value>> {
+        let passed = self.match_arguments(context, arguments)?;
+        self.resolve_with(context, passed)
+    }
 
-        for (input_args, named_args) in (&arguments, &self.arguments) {
-            match &arg.token.token_type {
-                TokenType::ID => {
-                    
-                },
-                TokenType::Auto(id) => {
-                    
-                },
-                _ => {}
+    /// Resolves the template with already evaluated positional arguments, used when it's applied as a value.
+    pub fn apply(&self, context: &mut Context, arguments: Vec<serde_json::Value>) -> JasonResult<Option<serde_json::Value>> {
+        let given = arguments.len();
+        let mut values: Vec<Option<serde_json::Value>> = vec![None; self.arguments.len()];
+        let rest_index = self.arguments.iter().position(|param| param.rest);
+        let fixed = rest_index.unwrap_or(self.arguments.len());
+        let mut rest: Vec<serde_json::Value> = Vec::new();
+        for (i, value) in arguments.into_iter().enumerate() {
+            if i < fixed {
+                values[i] = Some(value);
+            } else {
+                rest.push(value);
             }
         }
+        if let Some(index) = rest_index {
+            values[index] = Some(serde_json::Value::Array(rest));
+        }
 
-*/
+        self.check_arity(context, given, &values)?;
+        self.resolve_with(context, values)
+    }
+
+    /// The value a template evaluates to when it's referenced by name instead of called.
+    pub fn reference(name: &str) -> serde_json::Value {
+        let mut map = serde_json::Map::new();
+        map.insert(template_key().to_string(), serde_json::Value::String(name.to_string()));
+        serde_json::Value::Object(map)
+    }
 
-        // Save original variable state for keys we're overwriting
-        let mut old_values:HashMap<String, (serde_json::Value, JasonType)> = HashMap::new();
+    /// The name of the template `value` refers to, if it is a template reference.
+    pub fn referenced(value: &serde_json::Value) -> Option<&str> {
+        match value {
+            serde_json::Value::Object(map) if map.len() == 1 => map.get(template_key()).and_then(|name| name.as_str()),
+            _ => None,
+        }
+    }
 
+    /// The first template reference found anywhere inside `value`, including objects it was merged into.
+    pub fn find_reference(value: &serde_json::Value) -> Option<&str> {
+        match value {
+            serde_json::Value::Array(values) => values.iter().find_map(Self::find_reference),
+            serde_json::Value::Object(map) => match map.get(template_key()) {
+                Some(name) => Some(name.as_str().unwrap_or_default()),
+                None => map.values().find_map(Self::find_reference),
+            },
+            _ => None,
+        }
+    }
+
+    // the parameters are bound in a scope of their own, the block sees them and top level variables only
+    fn resolve_with(&self, context: &mut Context, passed: Vec<Option<serde_json::Value>>) -> JasonResult<Option<serde_json::Value>> {
+        // templates passed as values can reach themselves without naming themselves, like `T(f) { a: [f] map f }`
+        if context.call_depth() >= MAX_CALL_DEPTH {
+            return Err(context.err(
+                JasonErrorKind::RecursionLimit(self.name.clone()),
+                format!("template {} went past the call depth limit of {}, make sure its recursion has a base case", self.name, MAX_CALL_DEPTH)
+            ));
+        }
+        context.with_scope(Scope::closed(), |context| self.resolve_in_scope(context, passed))
+    }
+
+    fn resolve_in_scope(&self, context: &mut Context, passed: Vec<Option<serde_json::Value>>) -> JasonResult<Option<serde_json::Value>> {
         let (param_types, result_type) = if let Some((param_types, result_type)) = &self.typing {
-            (param_types, result_type)
+            (param_types.clone(), result_type.clone())
         } else {
-            (&vec![JasonType::Any; args.len()], &JasonType::Any)
+            (vec![JasonType::Any; self.arguments.len()], JasonType::Any)
         };
 
-        for (i, (key, value)) in args.iter().enumerate() {
-            if let Some(val) = context.variables.get(key).cloned() {
-                let typed_val = context.variable_types.get(key).unwrap_or(&JasonType::Any);
-                old_values.insert(key.clone(), (val, typed_val.clone()));
-            }
+        for (i, (param, passed)) in self.arguments.iter().zip(passed).enumerate() {
+            let key = &param.name;
+            // defaults are evaluated after the parameters before them are bound, so they can use them
+            let value = match (passed, &param.default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => context.to_json(default)?.ok_or_else(||
+                    context.err(JasonErrorKind::ValueError, format!("default {} of {} in template {} is None", default.plain_sum, key, self.name)))?,
+                (None, None) => unreachable!("arity is checked before binding"),
+            };
 
-            let typed_param = param_types.get(i).unwrap().clone();
+            let typed_param = param_types.get(i).cloned().unwrap_or(JasonType::Any);
 
-            if !typed_param.matches(value) {
-                let infered_type = context.infer_type_from(value)?;
+            if !typed_param.matches(&value) {
+                let infered_type = context.infer_type_from(&value)?;
                 return Err(context.err(
                     JasonErrorKind::TypeError(key.clone()),
                     format!(
@@ -121,9 +196,14 @@ impl Template {
                 ));
             }
 
-            context.add_var(key.clone(), value.clone(), typed_param);
+            context.bind(key.clone(), value);
         }
                 
+        let inherited = match &self.parent {
+            Some(parent) => Some(self.resolve_parent(context, parent)?),
+            None => None,
+        };
+
         // Evaluate block
         let block_node = &ASTNode::new(
             Token::new(token::TokenType::Block(self.block.clone()), "block".to_string(), 1, 1)
@@ -133,10 +213,19 @@ impl Template {
             context.err(JasonErrorKind::ValueError, format!("failed to evaluate block"))
         )?;
 
+        // the block's fields override the ones it inherited
+        let resolved_block = match (inherited, resolved_block) {
+            (Some(mut fields), serde_json::Value::Object(overrides)) => {
+                fields.extend(overrides);
+                serde_json::Value::Object(fields)
+            },
+            (_, resolved_block) => resolved_block,
+        };
+
         if !result_type.matches(&resolved_block) {
             let block_type = context.infer_type_from(&resolved_block)?;
              
-            match (result_type, &block_type) {
+            match (&result_type, &block_type) {
                 (JasonType::Object(o1), JasonType::Object(o2)) => {  
                     let diff = JasonType::diff_objects(&o1, &o2);
                     return Err(
@@ -152,22 +241,219 @@ impl Template {
             )
         }
         
-        // Restore old values
-        for (key, _) in args {
-            if let Some((old_val, type_val)) = old_values.remove(&key) {
-                context.add_var(key.clone(), old_val, type_val);
-            } else {
-                context.remove_var(key.clone());
-            }
+        if self.typing.is_none() {
+            self.check_inherited_type(context, &resolved_block)?;
         }
         
         Ok(Some(resolved_block))
     }
 
 
+    /// Evaluates the passed arguments in the caller's scope and lines them up with the parameters.
+    ///
+    /// Positional arguments come first, `...list` spreads a list into positional arguments,
+    /// then `name: value` ones follow in any order. Positional arguments past the last parameter
+    /// go into the `...rest` parameter if there is one. A parameter that wasn't passed is `None`.
+    fn match_arguments(&self, context: &mut Context, arguments: &token::Args) -> JasonResult<Vec<Option<serde_json::Value>>> {
+        let mut values: Vec<Option<serde_json::Value>> = vec![None; self.arguments.len()];
+        let rest_index = self.arguments.iter().position(|param| param.rest);
+        let fixed = rest_index.unwrap_or(self.arguments.len());
+        let mut rest: Vec<serde_json::Value> = Vec::new();
+        let mut given = 0;
+        let mut named = false;
+
+        for node in arguments {
+            let key = match (&node.token.token_type, node.left.as_ref()) {
+                (TokenType::Colon, Some(key)) if key.token.token_type == TokenType::ID => Some(key.token.plain()),
+                _ => None,
+            };
+            if key.is_none() && named {
+                return Err(context.err(
+                    JasonErrorKind::ArgumentError(node.plain_sum.clone()),
+                    format!("positional argument {} can't come after named arguments in template {}", node.plain_sum, self.name)
+                ));
+            }
+
+            let spread = node.token.token_type == TokenType::Spread;
+            let value_node = if key.is_some() || spread { node.right.as_deref().unwrap_or(node) } else { node };
+            let value = context.to_json(value_node)?.ok_or_else(||
+                context.err(JasonErrorKind::ValueError, format!("argument {} passed to template {} is None", value_node.plain_sum, self.name)))?;
+
+            let Some(key) = key else {
+                let positional = match (spread, value) {
+                    (false, value) => vec![value],
+                    (true, serde_json::Value::Array(items)) => items,
+                    (true, other) => {
+                        let found = context.infer_type_from(&other)?;
+                        return Err(context.err(
+                            JasonErrorKind::ArgumentError(node.plain_sum.clone()),
+                            format!("only lists can be spread into template {}, {} is {}", self.name, value_node.plain_sum, found)
+                        ));
+                    }
+                };
+                for value in positional {
+                    if given < fixed {
+                        values[given] = Some(value);
+                    } else if rest_index.is_some() {
+                        rest.push(value);
+                    }
+                    given += 1;
+                }
+                continue;
+            };
+
+            named = true;
+            let index = self.arguments.iter().position(|param| param.name == key).ok_or_else(|| {
+                let names: Vec<&str> = self.arguments.iter().map(|param| param.name.as_str()).collect();
+                context.err(
+                    JasonErrorKind::ArgumentError(key.clone()),
+                    format!("template {} has no parameter named {} (parameters: {})", self.name, key, names.join(", "))
+                )
+            })?;
+            if values[index].is_some() || (Some(index) == rest_index && !rest.is_empty()) {
+                return Err(context.err(
+                    JasonErrorKind::ArgumentError(key.clone()),
+                    format!("{} is passed more than once to template {}", key, self.name)
+                ));
+            }
+            values[index] = Some(value);
+            given += 1;
+        }
+
+        if let Some(index) = rest_index {
+            if values[index].is_none() {
+                values[index] = Some(serde_json::Value::Array(rest));
+            }
+        }
+
+        self.check_arity(context, given, &values)?;
+        Ok(values)
+    }
+
+    fn check_arity(&self, context: &Context, given: usize, values: &[Option<serde_json::Value>]) -> JasonResult<()> {
+        let missing: Vec<&str> = self.arguments
+            .iter()
+            .zip(values)
+            .filter(|(param, value)| value.is_none() && param.default.is_none())
+            .map(|(param, _)| param.name.as_str())
+            .collect();
+        let variadic = self.arguments.iter().any(|param| param.rest);
+        let total = self.arguments.len();
+        if (variadic || given <= total) && missing.is_empty() {
+            return Ok(());
+        }
+
+        let required = self.arguments.iter().filter(|param| param.default.is_none() && !param.rest).count();
+        let takes = if variadic {
+            format!("{} or more", required)
+        } else if required == total {
+            total.to_string()
+        } else {
+            format!("{} to {}", required, total)
+        };
+        let missing = if missing.is_empty() { String::new() } else { format!(", missing {}", missing.join(", ")) };
+        Err(context.err(
+            JasonErrorKind::ArityError(self.name.clone()),
+            format!("template {} takes {} argument{} but {} {} given{}",
+                self.name, takes, if total == 1 && !variadic { "" } else { "s" }, given, if given == 1 { "was" } else { "were" }, missing)
+        ))
+    }
+
+    fn resolve_parent(&self, context: &mut Context, parent: &ASTNode) -> JasonResult<serde_json::Map<String, serde_json::Value>> {
+        let parent_name = parent.token.plain();
+        let template = context.templates.get(&parent_name).cloned().ok_or_else(||
+            context.err(
+                JasonErrorKind::UndefinedTemplate(parent_name.clone()),
+                format!("template {} extends {} which does not exist in file {}", self.name, parent_name, context.source_path)
+            )
+        )?;
+        // checked again here since the parent may have been defined after this template
+        if parent_name == self.name || Self::extends(context, &parent_name, &self.name) {
+            return Err(context.err(
+                JasonErrorKind::TemplateRescursion(self.name.clone()),
+                format!("template {} extends itself through {}", self.name, parent_name)
+            ));
+        }
+
+        let args = match &parent.token.token_type {
+            TokenType::FnCall(args) => args.clone(),
+            _ => Vec::new(),
+        };
+        match template.resolve(context, &args)? {
+            Some(serde_json::Value::Object(fields)) => Ok(fields),
+            Some(other) => {
+                let found = context.infer_type_from(&other)?;
+                Err(context.err(
+                    JasonErrorKind::TypeError(parent_name.clone()),
+                    format!("template {} extends {} which resolved to {}, only templates that result in objects can be extended", self.name, parent_name, found)
+                ))
+            },
+            None => Err(context.err(JasonErrorKind::ValueError, format!("template {} resolved to None", parent_name))),
+        }
+    }
+
+    // whether `template` extends `ancestor`, directly or further up the chain
+    fn extends(context: &Context, template: &str, ancestor: &str) -> bool {
+        let mut seen = HashSet::new();
+        let mut current = template.to_string();
+        while let Some(parent) = context.templates.get(&current).and_then(|t| t.parent.as_ref()) {
+            let parent_name = parent.token.plain();
+            if parent_name == ancestor {
+                return true;
+            }
+            if !seen.insert(parent_name.clone()) {
+                return false;
+            }
+            current = parent_name;
+        }
+        false
+    }
+
+    /// A template without typing of its own keeps the typing of the closest template it extends that has one.
+    ///
+    /// Its fields still have to match, but fields the template adds are allowed.
+    fn check_inherited_type(&self, context: &mut Context, value: &serde_json::Value) -> JasonResult<()> {
+        let mut seen = HashSet::new();
+        let mut parent = self.parent.as_ref().map(|p| p.token.plain());
+        let (ancestor, result_type) = loop {
+            let Some(name) = parent.filter(|name| seen.insert(name.clone())) else {
+                return Ok(());
+            };
+            let Some(template) = context.templates.get(&name) else {
+                return Ok(());
+            };
+            if let Some((_, result_type)) = &template.typing {
+                break (name, result_type.clone());
+            }
+            parent = template.parent.as_ref().map(|p| p.token.plain());
+        };
+
+        let mismatch = match (&result_type, value) {
+            (JasonType::Object(fields), serde_json::Value::Object(obj)) => fields
+                .iter()
+                .find(|(key, field_type)| !obj.get(*key).is_some_and(|v| field_type.matches(v)))
+                .map(|(key, field_type)| (key.clone(), field_type.clone(), obj.get(key).cloned())),
+            (result_type, value) if !result_type.matches(value) => Some((self.name.clone(), result_type.clone(), Some(value.clone()))),
+            _ => None,
+        };
+
+        if let Some((key, expected, found)) = mismatch {
+            let found = match found {
+                Some(found) => context.infer_type_from(&found)?.to_string(),
+                None => "nothing".to_string(),
+            };
+            return Err(context.err(
+                JasonErrorKind::TypeError(key.clone()),
+                format!("{} in template {} must be {} as typed for {}, found {}", key, self.name, expected, ancestor, found)
+            ));
+        }
+        Ok(())
+    }
+
     pub fn check_self_reference(context: &Context, top_level: &ASTNode, node: &ASTNode, name: &str) -> JasonResult<()> {
         if let token::TokenType::FnCall(_) = &node.token.token_type {
-            if node.token.plain() == name {
+            // calling or extending a template that extends this one recurses just the same
+            if node.token.plain() == name || Self::extends(context, &node.token.plain(), name) {
                 return Err(
                     JasonError::new(
                         JasonErrorKind::TemplateRescursion(name.to_string()), 
@@ -2,6 +2,7 @@
 
 use crate::parser::Parser;
 use crate::astnode::ASTNode;
+use crate::builtins;
 use crate::jason_errors::{JasonError};
 
 pub type Args = Vec<ASTNode>;
@@ -21,9 +22,18 @@ pub enum TokenType {
     FnCall(Args),
     LuaFnCall(Args),
     Map(Args),
+    Filter(Args),
+    Reduce(Args),
+    // `by(x)` after `sort` and `group`
+    By(Args),
+    // a call to one of `builtins::BUILTINS`, the name is the token's plain
+    Builtin(Args),
     Index(Args),
-    // input args, block args
-    TemplateDef(Args, Args),
+    OptionalIndex(Args),
+    // input args, block args, the template it extends
+    TemplateDef(Args, Args, Option<Box<ASTNode>>),
+    // `name(params) =>`, the parser reads the body after it
+    FnDef(Args),
     //StructDef(Box<Token>, Args),
     Block(Args),
     List(Args),
@@ -38,6 +48,9 @@ pub enum TokenType {
     DoubleColon,
     Colon,
     Dot,
+    QuestionDot,
+    DoubleQuestion,
+    Spread,
     //whitespace
     NewLine,
     Comma,
@@ -66,6 +79,11 @@ pub enum TokenType {
     GreaterThan,
     LessThanEqualTo,
     GreaterThanEqualTo,
+    DoubleEquals,
+    NotEquals,
+    And,
+    Or,
+    Not,
     // keywords
     StringConverion(Args),
     IntConverion(Args),
@@ -102,7 +120,15 @@ pub enum TokenType {
     Include,
     Info,
     InfoT,
-    Auto(String)
+    Auto(String),
+    If,
+    Then,
+    Else,
+    In,
+    Sort,
+    Unique,
+    Reverse,
+    Group,
 }
 
 impl TokenType {
@@ -134,6 +160,14 @@ impl TokenType {
             "info"   => TokenType::Info,
             "infoT"   => TokenType::InfoT,
             "include" => TokenType::Include,
+            "if"     => TokenType::If,
+            "then"   => TokenType::Then,
+            "else"   => TokenType::Else,
+            "and"    => TokenType::And,
+            "or"     => TokenType::Or,
+            "not"    => TokenType::Not,
+            "let"    => TokenType::Let,
+            "in"     => TokenType::In,
             _ => TokenType::ID
         }
     }
@@ -251,7 +285,11 @@ impl Token {
                 "int" => Token::new(TokenType::IntConverion(args), name.to_string(), row, colmn),
                 "float" => Token::new(TokenType::FloatConverion(args), name.to_string(), row, colmn),
                 "map" => Token::new(TokenType::Map(args), name.to_string(), row, colmn),
+                "filter" => Token::new(TokenType::Filter(args), name.to_string(), row, colmn),
+                "reduce" => Token::new(TokenType::Reduce(args), name.to_string(), row, colmn),
+                "by" => Token::new(TokenType::By(args), name.to_string(), row, colmn),
                 "use" => Token::new(TokenType::Use(args), name.to_string(), row, colmn),
+                name if builtins::is_builtin(name) => Token::new(TokenType::Builtin(args), name.to_string(), row, colmn),
                 _ => self,
             } 
         }
@@ -267,8 +305,11 @@ impl Token {
             "int" |
             "float" |
             "map" |
+            "filter" |
+            "reduce" |
+            "by" |
             "use" => true,
-            _ => false,
+            _ => builtins::is_builtin(name),
         } 
     }  
 
@@ -339,6 +380,10 @@ impl Token {
             // ===== Function calls =====
             TokenType::FnCall(args)
             | TokenType::Map(args)
+            | TokenType::Filter(args)
+            | TokenType::Reduce(args)
+            | TokenType::By(args)
+            | TokenType::Builtin(args)
             | TokenType::Import(args)
             | TokenType::Export(args)
             | TokenType::StringConverion(args)
@@ -359,12 +404,23 @@ impl Token {
                 let inside = args.as_string_list();
                 format!("{}{}", self.plain, inside)
             }
+            TokenType::OptionalIndex(args) => {
+                let inside = args.as_string_list();
+                format!("{}?{}", self.plain, inside)
+            }
 
             // ===== Template definitions =====
-            TokenType::TemplateDef(input_args, block_args) => {
+            TokenType::TemplateDef(input_args, block_args, parent) => {
                 let inputs = input_args.as_string_tuple();
                 let blocks = block_args.as_string_tuple();
-                format!("template {} {}", inputs, blocks)
+                match parent {
+                    Some(parent) => format!("template {} extends {} {}", inputs, parent.token.pretty(), blocks),
+                    None => format!("template {} {}", inputs, blocks),
+                }
+            }
+
+            TokenType::FnDef(params) => {
+                format!("{}{} =>", self.plain, params.as_string_tuple())
             }
 
             // ===== Templates =====
@@ -397,6 +453,9 @@ impl Token {
             TokenType::DoubleColon   => "::".to_string(),
             TokenType::Colon         => ":".to_string(),
             TokenType::Dot           => ".".to_string(),
+            TokenType::QuestionDot   => "?.".to_string(),
+            TokenType::DoubleQuestion => "??".to_string(),
+            TokenType::Spread        => "...".to_string(),
             TokenType::Comma         => ",".to_string(),
             TokenType::OpenParen     => "(".to_string(),
             TokenType::ClosedParen   => ")".to_string(),
@@ -421,6 +480,11 @@ impl Token {
 
             TokenType::LessThanEqualTo    => "<=".to_string(),
             TokenType::GreaterThanEqualTo => ">=".to_string(),
+            TokenType::DoubleEquals       => "==".to_string(),
+            TokenType::NotEquals          => "!=".to_string(),
+            TokenType::And                => "and".to_string(),
+            TokenType::Or                 => "or".to_string(),
+            TokenType::Not                => "not".to_string(),
 
             // ===== Misc =====
             TokenType::Return     => "return".to_string(),
@@ -451,6 +515,14 @@ impl Token {
             TokenType::InfoT      => "infoT".to_string(),
             TokenType::Include    => "include".to_string(),
             TokenType::Auto(var)  => format!("*{}", var),
+            TokenType::If         => "if".to_string(),
+            TokenType::Then       => "then".to_string(),
+            TokenType::Else       => "else".to_string(),
+            TokenType::In         => "in".to_string(),
+            TokenType::Sort       => "sort".to_string(),
+            TokenType::Unique     => "unique".to_string(),
+            TokenType::Reverse    => "reverse".to_string(),
+            TokenType::Group      => "group".to_string(),
             
             TokenType::DollarSign => "$".to_string(),
 
@@ -1,4 +1,4 @@
-use jason_rs::{JasonBuilder, jason_src_to_json};
+use jason_rs::{JasonBuilder, OutputFormat, jason_src_check, jason_src_to_json, jason_src_to_toml, jason_src_to_yaml};
 use serde_json::json;
 
 #[test]
@@ -696,3 +696,1454 @@ fn test_arithmetic_with_variables() {
     
     assert_eq!(result, expected);
 }
+
+#[test]
+fn test_yaml_output() {
+    let jason = r#"
+        out {
+            containers: [{ image: "nginx:1.25", name: "nginx" }],
+            debug: false,
+            empty: [],
+            labels: { app: "web", tier: "frontend" },
+            name: "web",
+            owner: null,
+            ports: [80, 443],
+            replicas: 3
+        }
+    "#;
+
+    let result = jason_src_to_yaml(jason).expect("failed to compile");
+    let expected = "\
+containers:
+  - image: nginx:1.25
+    name: nginx
+debug: false
+empty: []
+labels:
+  app: web
+  tier: frontend
+name: web
+owner: null
+ports:
+  - 80
+  - 443
+replicas: 3
+";
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_yaml_scalar_quoting() {
+    let jason = r##"
+        out ["yes", "null", "1.10", "-5", "", " padded", "a: b", "#tag", "plain text", "key:value"]
+    "##;
+
+    let result = jason_src_to_yaml(jason).expect("failed to compile");
+    let expected = "\
+- \"yes\"
+- \"null\"
+- \"1.10\"
+- \"-5\"
+- \"\"
+- \" padded\"
+- \"a: b\"
+- \"#tag\"
+- plain text
+- key:value
+";
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_yaml_multiline_strings() {
+    let jason = "out { note: \"a\nb\", script: \"echo hi\necho bye\n\" }";
+
+    let result = jason_src_to_yaml(jason).expect("failed to compile");
+    let expected = "\
+note: |-
+  a
+  b
+script: |
+  echo hi
+  echo bye
+";
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_yaml_round_trip() {
+    let jason = r#"
+        out {
+            newline: "\n",
+            newlines: "\n\n",
+            nested: { inner: "\n", list: ["\n", "=", "<<"] },
+            merge: "<<",
+            value: "=",
+            script: "echo hi\n",
+            tabbed: "\tx\ny",
+            separators: [["a\u{2028}b", "c\u{2029}d"]],
+            separated_lines: "a\u{2028}b\nc"
+        }
+    "#;
+
+    let yaml = jason_src_to_yaml(jason).expect("failed to compile");
+    let json = jason_src_to_json(jason).expect("failed to compile");
+    let parsed: serde_json::Value = serde_yaml::from_str(&yaml).expect("failed to parse the yaml");
+    assert_eq!(parsed, json, "{}", yaml);
+    assert!(yaml.contains("newline: \"\\n\"\n"), "{}", yaml);
+    assert!(yaml.contains("value: \"=\"\n") && yaml.contains("merge: \"<<\"\n"), "{}", yaml);
+    assert!(yaml.contains("tabbed: \"\\tx\\ny\"\n"), "{}", yaml);
+    assert!(yaml.contains("- - \"a\\Lb\"\n") && yaml.contains("  - \"c\\Pd\"\n"), "{}", yaml);
+
+    for top in ["out \"\\n\"", "out [\"\\n\"]"] {
+        let yaml = jason_src_to_yaml(top).expect("failed to compile");
+        let parsed: serde_json::Value = serde_yaml::from_str(&yaml).expect("failed to parse the yaml");
+        assert_eq!(parsed, jason_src_to_json(top).unwrap(), "{}", yaml);
+    }
+}
+
+#[test]
+fn test_toml_output() {
+    let jason = r#"
+        out {
+            database: { server: { host: "localhost", port: 5432 } },
+            owner: { email: "alex@example.com", name: "alex" },
+            ratio: 0.5,
+            servers: [{ name: "alpha" }, { name: "beta", ports: [80, 443] }],
+            tags: ["web", "prod"],
+            title: "site",
+            version: 2
+        }
+    "#;
+
+    let result = jason_src_to_toml(jason).expect("failed to compile");
+    let expected = "\
+ratio = 0.5
+tags = [\"web\", \"prod\"]
+title = \"site\"
+version = 2
+
+[database.server]
+host = \"localhost\"
+port = 5432
+
+[owner]
+email = \"alex@example.com\"
+name = \"alex\"
+
+[[servers]]
+name = \"alpha\"
+
+[[servers]]
+name = \"beta\"
+ports = [80, 443]
+";
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_toml_rejects_non_table_out() {
+    let result = jason_src_to_toml(r#"out [1, 2, 3]"#);
+    assert!(result.is_err(), "a top-level list can't be written as TOML");
+}
+
+#[test]
+fn test_toml_rejects_null() {
+    let result = jason_src_to_toml(r#"out { name: "alex", nickname: null }"#);
+    assert!(result.is_err(), "null values can't be written as TOML");
+}
+
+#[test]
+fn test_toml_rejects_mixed_table_arrays() {
+    let result = jason_src_to_toml(r#"out { items: [{ id: 1 }, 2] }"#);
+    assert!(result.is_err(), "lists mixing objects and values can't be written as TOML");
+}
+
+#[cfg(feature = "preserve_order")]
+#[test]
+fn test_preserve_key_order() {
+    let jason = r#"
+        Person(name, project) {
+            name: name,
+            project: project,
+            money: 20
+        }
+        alex := Person("alex", "jason")
+        out {
+            zeta: alex + { id: 1 },
+            alpha: alex & { money: 5, bonus: 2 },
+            mid: [1] map(n) { z: n, a: n }
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let rendered = serde_json::to_string(&result).unwrap();
+    let expected = concat!(
+        r#"{"zeta":{"name":"alex","project":"jason","money":20,"id":1},"#,
+        r#""alpha":{"name":"alex","project":"jason","money":20,"bonus":2},"#,
+        r#""mid":[{"z":1,"a":1}]}"#
+    );
+
+    assert_eq!(rendered, expected);
+}
+
+#[cfg(feature = "preserve_order")]
+#[test]
+fn test_preserve_key_order_yaml() {
+    let jason = r#"
+        out { name: "web", kind: "Service", apiVersion: "v1" }
+    "#;
+
+    let result = jason_src_to_yaml(jason).expect("failed to compile");
+    assert_eq!(result, "name: web\nkind: Service\napiVersion: v1\n");
+}
+
+#[test]
+fn test_error_display_includes_source_line() {
+    let err = jason_src_to_json("out missing_var").expect_err("undefined variable should fail");
+    let rendered = err.to_string();
+    assert!(rendered.contains("out missing_var"), "error should show the offending line: {}", rendered);
+}
+
+#[test]
+fn test_check_reports_every_error() {
+    let jason = r#"
+        a := missing_one
+        b := missing_two
+        out { ok: true }
+    "#;
+
+    let report = jason_src_check(jason);
+    assert!(!report.passed());
+    assert_eq!(report.errors.len(), 2, "bundled errors should be reported individually");
+
+    let report = jason_src_check(r#"out { ok: true }"#);
+    assert!(report.passed());
+}
+
+#[test]
+fn test_watch_recompiles_on_import_change() {
+    let dir = std::env::temp_dir().join(format!("jason_watch_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let main_path = dir.join("main.jason");
+    let dev_path = dir.join("dev.jason");
+
+    std::fs::write(&dev_path, "Dev(name) {\n    name: name\n}\n").unwrap();
+    std::fs::write(
+        &main_path,
+        format!("import(Dev) from \"{}\"\nout Dev(\"alex\")\n", dev_path.display()),
+    ).unwrap();
+
+    let mut watcher = JasonBuilder::new().watch(main_path.to_str().unwrap(), OutputFormat::Json);
+    let first = watcher.poll().expect("first poll always compiles").expect("failed to compile");
+    assert_eq!(first, r#"{"name":"alex"}"#);
+    assert!(watcher.poll().is_none(), "nothing changed since the last poll");
+    assert_eq!(watcher.watched_files().len(), 2);
+
+    std::fs::write(&dev_path, "Dev(name) {\n    dev: name\n}\n").unwrap();
+    // make sure the change is visible even on filesystems with coarse timestamps
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+    std::fs::File::options().write(true).open(&dev_path).unwrap().set_modified(later).unwrap();
+
+    let second = watcher.poll().expect("import changed").expect("failed to compile");
+    assert_eq!(second, r#"{"dev":"alex"}"#);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_if_then_else() {
+    let jason = r#"
+        prod := true
+        debug := false
+        Service(prod) {
+            replicas: if prod then 3 else 1,
+            tier: if prod then "gold" else if debug then "debug" else "basic"
+        }
+        out {
+            prod: Service(prod),
+            dev: Service(false),
+            lazy: if debug then missing_var else "skipped"
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "prod": { "replicas": 3, "tier": "gold" },
+        "dev": { "replicas": 1, "tier": "basic" },
+        "lazy": "skipped"
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_if_condition_must_be_bool() {
+    let result = jason_src_to_json(r#"out if 1 then "a" else "b""#);
+    assert!(result.is_err(), "a non-Bool condition should be a type error");
+
+    let result = jason_src_to_json(r#"out if true then "a""#);
+    assert!(result.is_err(), "an if without else should fail to parse");
+}
+
+#[test]
+fn test_keywords_as_keys() {
+    let jason = r#"
+        out {if: 1, then: 2, else: if true then 3 else 4}
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    assert_eq!(result, json!({ "if": 1, "then": 2, "else": 3 }));
+}
+
+#[test]
+fn test_comparison_operators() {
+    let jason = r#"
+        replicas := 3
+        env := "prod"
+        out {
+            lt: 1 < 2,
+            le: 2 <= 2,
+            gt: 1.5 > 2,
+            ge: "b" >= "a",
+            eq: env == "prod",
+            ne: [1, 2] != [1, 2],
+            mixed_numbers: 1 == 1.0,
+            objects: { a: 1 } == { a: 1 },
+            arithmetic: replicas * 2 > replicas + 2,
+            mapped: [1, 5, 10] map(n) n > 4
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "lt": true,
+        "le": true,
+        "gt": false,
+        "ge": true,
+        "eq": true,
+        "ne": false,
+        "mixed_numbers": true,
+        "objects": true,
+        "arithmetic": true,
+        "mapped": [false, true, true]
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_boolean_operators() {
+    let jason = r#"
+        env := "prod"
+        replicas := 3
+        out {
+            both: env == "prod" and replicas > 2,
+            either: env == "dev" or replicas == 3,
+            negated: not env == "dev",
+            precedence: true or false and false,
+            short_circuit: false and missing_var,
+            branch: if env != "prod" or replicas < 1 then "scale" else "keep"
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "both": true,
+        "either": true,
+        "negated": true,
+        "precedence": true,
+        "short_circuit": false,
+        "branch": "keep"
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_boolean_keywords_as_keys() {
+    let jason = r#"
+        out {and: 1, or: 2, not: not false and true}
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    assert_eq!(result, json!({ "and": 1, "or": 2, "not": true }));
+}
+
+#[test]
+fn test_comparison_type_errors() {
+    assert!(jason_src_to_json(r#"out 1 < "a""#).is_err(), "numbers and strings can't be ordered");
+    assert!(jason_src_to_json(r#"out 1 and true"#).is_err(), "and needs Bool operands");
+    assert!(jason_src_to_json(r#"out not 1"#).is_err(), "not needs a Bool operand");
+}
+
+#[test]
+fn test_intervals_still_work_as_types() {
+    let jason = r#"
+        Age :: >= 0 while < 130
+        age: Age = 30
+        pos: > 0 = 3
+        out { age: age, pos: pos, check: age >= 18 }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    assert_eq!(result, json!({ "age": 30, "pos": 3, "check": true }));
+
+    assert!(jason_src_to_json(r#"age: >= 0 = -1 out age"#).is_err(), "interval types still reject values");
+}
+
+#[test]
+fn test_string_escapes() {
+    let jason = r#"
+        out {
+            newline: "a\nb",
+            tab: "a\tb",
+            quote: "say \"hi\"",
+            slash: "back\\slash",
+            braced: "\u{1F600}",
+            json_style: "caf\u00E9",
+            surrogates: "\uD83D\uDE00",
+            composite: $"x\t{1 + 1}\n\{literal\}"
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "newline": "a\nb",
+        "tab": "a\tb",
+        "quote": "say \"hi\"",
+        "slash": "back\\slash",
+        "braced": "😀",
+        "json_style": "café",
+        "surrogates": "😀",
+        "composite": "x\t2\n{literal}"
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_invalid_string_escapes() {
+    assert!(jason_src_to_json(r#"out "bad \q""#).is_err(), "unknown escape");
+    assert!(jason_src_to_json(r#"out $"bad \q {1}""#).is_err(), "unknown escape in composite string");
+    assert!(jason_src_to_json(r#"out "\uD83D""#).is_err(), "unpaired high surrogate");
+    assert!(jason_src_to_json(r#"out "\uDE00""#).is_err(), "lone low surrogate");
+    assert!(jason_src_to_json(r#"out "\u{110000}""#).is_err(), "out of range code point");
+    assert!(jason_src_to_json(r#"out "\u12""#).is_err(), "too few hex digits");
+}
+
+#[test]
+fn test_multiline_strings() {
+    let jason = r#"
+        user := "alex"
+        out {
+            script: """
+                #!/bin/sh
+                if [ -f "$HOME/.profile" ]; then
+                    echo "loaded"
+                fi
+                """,
+            inline: """one "quoted" line""",
+            outdented: """
+                closing quotes can sit further left
+            """,
+            escaped: """
+                tab\there
+                  \"""
+                """,
+            greeting: $"""
+                hello {user}
+                  id: {1 + 1}
+                """
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "script": "#!/bin/sh\nif [ -f \"$HOME/.profile\" ]; then\n    echo \"loaded\"\nfi",
+        "inline": "one \"quoted\" line",
+        "outdented": "closing quotes can sit further left",
+        "escaped": "tab\there\n  \"\"\"",
+        "greeting": "hello alex\n  id: 2"
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_raw_strings() {
+    let jason = r####"
+        out {
+            path: r"C:\Users\alex\{not interpolated}",
+            quoted: r#"say "hi" \n"#,
+            nested: r##"contains "# inside"##,
+            pem: r"-----BEGIN CERTIFICATE-----
+MIIB
+-----END CERTIFICATE-----"
+        }
+    "####;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "path": "C:\\Users\\alex\\{not interpolated}",
+        "quoted": "say \"hi\" \\n",
+        "nested": "contains \"# inside",
+        "pem": "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----"
+    });
+
+    assert_eq!(result, expected);
+
+    assert!(jason_src_to_json(r#"out """never closed"#).is_err(), "unterminated multi-line string");
+    assert!(jason_src_to_json(r##"out r#"never closed""##).is_err(), "unterminated raw string");
+}
+
+#[test]
+fn test_path_access() {
+    let jason = r#"
+        config := {
+            db: { host: "localhost", port: 5432, type: "postgres" },
+            items: [{ name: "first" }, { name: "second", tags: ["a", "b"] }]
+        }
+        grid := [[1, 2], [3, 4]]
+        out {
+            host: config.db.host,
+            kind: config.db.type,
+            name: config.items[1].name,
+            tag: config.items[1].tags[0],
+            cell: grid[1][0],
+            computed: grid[0][1 + 0] * 10,
+            last: config.items[config.db.port - 5431].name,
+            letter: config.db.host[0],
+            literal: "abc"[1],
+            compared: config.db.port > 5000
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "host": "localhost",
+        "kind": "postgres",
+        "name": "second",
+        "tag": "a",
+        "cell": 3,
+        "computed": 20,
+        "last": "second",
+        "letter": "l",
+        "literal": "b",
+        "compared": true
+    });
+
+    assert_eq!(result, expected);
+
+    // a list after a space or an operator is still a list
+    let lists = jason_src_to_json(r#"x := [1] out { a: [2], b: x + [3] }"#).expect("failed to compile");
+    assert_eq!(lists, json!({ "a": [2], "b": [1, 3] }));
+}
+
+#[test]
+fn test_path_access_errors() {
+    let err = jason_src_to_json(r#"config := { db: { host: "h" } } out config.db.port"#).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("config.db.port"), "names the full path: {}", message);
+    assert!(message.contains("\"port\""), "names the missing segment: {}", message);
+
+    let err = jason_src_to_json(r#"items := [1, 2] out items[5]"#).unwrap_err();
+    assert!(err.to_string().contains("items[5]"), "{}", err);
+
+    assert!(jason_src_to_json(r#"n := 1 out n.x"#).is_err(), "numbers have no keys");
+    assert!(jason_src_to_json(r#"items := [1] out items["a"]"#).is_err(), "lists need number indexes");
+}
+
+#[test]
+fn test_optional_access_and_defaults() {
+    let jason = r#"
+        cfg := { db: { host: "localhost" }, replicas: [{ zone: "a" }] }
+        none := null
+        out {
+            missing: cfg?.db?.port,
+            present: cfg?.db?.host,
+            skipped: cfg?.cache.size.bytes,
+            from_null: none?.anything,
+            index: cfg.replicas?[3],
+            zone: cfg.replicas?[0]?.zone,
+            port: cfg?.db?.port ?? 5432,
+            chained: cfg?.cache ?? cfg?.fallback ?? "none",
+            kept: cfg.db.host ?? "unused",
+            falsy: false ?? true,
+            summed: cfg?.db?.port ?? 5000 + 432
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "missing": null,
+        "present": "localhost",
+        "skipped": null,
+        "from_null": null,
+        "index": null,
+        "zone": "a",
+        "port": 5432,
+        "chained": "none",
+        "kept": "localhost",
+        "falsy": false,
+        "summed": 5432
+    });
+
+    assert_eq!(result, expected);
+
+    // plain access still reports what's missing
+    assert!(jason_src_to_json(r#"cfg := { db: {} } out cfg.db.port"#).is_err());
+    // `?.` only covers missing data, not indexing the wrong kind of value
+    assert!(jason_src_to_json(r#"n := 1 out n?.port"#).is_err());
+}
+
+#[test]
+fn test_optional_access_types() {
+    // the type of an optional value allows Null, so a later default still type checks
+    let jason = r#"
+        cfg := { port: 80 }
+        port := cfg?.port
+        port = null
+        missing := cfg?.host
+        missing = "anything"
+        out { port: port, missing: missing }
+    "#;
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    assert_eq!(result, json!({ "port": null, "missing": "anything" }));
+
+    let err = jason_src_to_json(r#"cfg := {} port: Int = cfg?.port out port"#).unwrap_err();
+    assert!(err.to_string().contains("??"), "suggests a default: {}", err);
+    assert!(jason_src_to_json(r#"cfg := {} port: Int = cfg?.port ?? 80 out port"#).is_ok());
+}
+
+#[test]
+fn test_template_default_parameters() {
+    let jason = r#"
+        Dev(name, project = "misc", money = 0) {
+            name: name,
+            project: project,
+            money: money
+        }
+        Tagged(name, *tag = name + "-tag") {
+            name: name
+        }
+        out [
+            Dev("alex"),
+            Dev("alex", "jason-rs"),
+            Dev("alex", "jason-rs", 10),
+            Tagged("api"),
+            Tagged("api", "custom")
+        ]
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!([
+        { "name": "alex", "project": "misc", "money": 0 },
+        { "name": "alex", "project": "jason-rs", "money": 0 },
+        { "name": "alex", "project": "jason-rs", "money": 10 },
+        { "name": "api", "tag": "api-tag" },
+        { "name": "api", "tag": "custom" }
+    ]);
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_template_defaults_with_typing() {
+    let jason = r#"
+        Dev(String, String, Int) :: { name: String, project: String, money: Int }
+        Dev(name, project = "misc", money = 0) {
+            name: name,
+            project: project,
+            money: money
+        }
+        out Dev("alex")
+    "#;
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    assert_eq!(result, json!({ "name": "alex", "project": "misc", "money": 0 }));
+
+    let bad_default = r#"
+        Dev(String, Int) :: { name: String, money: Int }
+        Dev(name, money = "lots") { name: name, money: money }
+        out Dev("alex")
+    "#;
+    assert!(jason_src_to_json(bad_default).is_err(), "defaults are type checked too");
+}
+
+#[test]
+fn test_template_arity_errors() {
+    let defs = r#"Dev(name, project = "misc") { name: name, project: project }"#;
+
+    let err = jason_src_to_json(&format!("{} out Dev()", defs)).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("Arity Error"), "{}", message);
+    assert!(message.contains("Dev") && message.contains("missing name"), "{}", message);
+
+    let err = jason_src_to_json(&format!(r#"{} out Dev("a", "b", "c")"#, defs)).unwrap_err();
+    assert!(err.to_string().contains("takes 1 to 2 arguments but 3 were given"), "{}", err);
+
+    assert!(
+        jason_src_to_json(r#"Dev(project = "misc", name) { name: name } out Dev("a")"#).is_err(),
+        "required parameters can't follow defaults"
+    );
+}
+
+#[test]
+fn test_template_named_arguments() {
+    let jason = r#"
+        User(String, String, Int) :: { email: String, password: String, ip: Int }
+        User(email, password, ip = 0) {
+            email: email,
+            password: password,
+            ip: ip
+        }
+        out [
+            User(email: "a@b", ip: 1, password: "x"),
+            User("a@b", ip: 2, password: "y"),
+            User("a@b", "z")
+        ]
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!([
+        { "email": "a@b", "password": "x", "ip": 1 },
+        { "email": "a@b", "password": "y", "ip": 2 },
+        { "email": "a@b", "password": "z", "ip": 0 }
+    ]);
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_template_named_argument_errors() {
+    let defs = r#"
+        User(String, String) :: { email: String, password: String }
+        User(email, password) { email: email, password: password }
+    "#;
+    let compile = |call: &str| jason_src_to_json(&format!("{} out {}", defs, call)).unwrap_err().to_string();
+
+    let message = compile(r#"User(email: "a", pasword: "x")"#);
+    assert!(message.contains("Argument Error") && message.contains("no parameter named pasword"), "{}", message);
+
+    let message = compile(r#"User("a", email: "b")"#);
+    assert!(message.contains("email is passed more than once"), "{}", message);
+
+    let message = compile(r#"User(email: "a", "x")"#);
+    assert!(message.contains("can't come after named arguments"), "{}", message);
+
+    let message = compile(r#"User(password: "x")"#);
+    assert!(message.contains("missing email"), "{}", message);
+
+    let message = compile(r#"User(password: 1, email: "a")"#);
+    assert!(message.contains("expected type String for password"), "named arguments are type checked: {}", message);
+}
+
+#[test]
+fn test_variadic_templates() {
+    let jason = r#"
+        Tags(name, ...tags) {
+            name: name,
+            tags: tags
+        }
+        Auto(kind = "plain", ...*labels) {
+            kind: kind
+        }
+        out {
+            none: Tags("api"),
+            some: Tags("api", "web", "v2"),
+            auto: Auto(),
+            labelled: Auto("fancy", 1, 2)
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "none": { "name": "api", "tags": [] },
+        "some": { "name": "api", "tags": ["web", "v2"] },
+        "auto": { "kind": "plain", "labels": [] },
+        "labelled": { "kind": "fancy", "labels": [1, 2] }
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_spread_arguments() {
+    let jason = r#"
+        Dev(name, project, money) { name: name, project: project, money: money }
+        Tags(name, ...tags) { name: name, tags: tags }
+        row := ["alex", "jason-rs", 0]
+        extra := ["b", "c"]
+        out {
+            spread: Dev(...row),
+            mixed: Dev("alex", ...["misc"], money: 5),
+            rest: Tags("a", ...extra, "d"),
+            rest_named: Tags(name: "a", tags: ["x"])
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "spread": { "name": "alex", "project": "jason-rs", "money": 0 },
+        "mixed": { "name": "alex", "project": "misc", "money": 5 },
+        "rest": { "name": "a", "tags": ["b", "c", "d"] },
+        "rest_named": { "name": "a", "tags": ["x"] }
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_variadic_typing_and_errors() {
+    let typed = r#"
+        Tags(String, [String]) :: { name: String, tags: [String] }
+        Tags(name, ...tags) { name: name, tags: tags }
+    "#;
+    let ok = jason_src_to_json(&format!(r#"{} out Tags("a", "b")"#, typed)).expect("failed to compile");
+    assert_eq!(ok, json!({ "name": "a", "tags": ["b"] }));
+    assert!(jason_src_to_json(&format!(r#"{} out Tags("a", 1)"#, typed)).is_err(), "rest values are type checked as a list");
+
+    let dev = r#"Dev(name, project) { name: name }"#;
+    let err = jason_src_to_json(&format!(r#"{} out Dev(..."alex")"#, dev)).unwrap_err();
+    assert!(err.to_string().contains("only lists can be spread"), "{}", err);
+    let err = jason_src_to_json(&format!(r#"{} out Dev(...["a", "b", "c"])"#, dev)).unwrap_err();
+    assert!(err.to_string().contains("takes 2 arguments but 3 were given"), "{}", err);
+
+    assert!(jason_src_to_json(r#"T(...rest, name) { name: name } out T(1)"#).is_err(), "rest has to be last");
+    assert!(jason_src_to_json(r#"x := [1] out ...x"#).is_err(), "spread only works in arguments");
+}
+
+#[test]
+fn test_template_extends() {
+    let jason = r#"
+        BaseService(name, replicas = 1) {
+            name: name,
+            replicas: replicas,
+            kind: "service"
+        }
+        WebService(name, port) extends BaseService(name, replicas: 2) {
+            port: port,
+            kind: "web"
+        }
+        ApiService(name, version) extends WebService(name + "-api", 443) {
+            version: version
+        }
+        out {
+            web: WebService("site", 80),
+            api: ApiService("users", "v2")
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "web": { "name": "site", "replicas": 2, "kind": "web", "port": 80 },
+        "api": { "name": "users-api", "replicas": 2, "kind": "web", "port": 443, "version": "v2" }
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_template_extends_typing() {
+    let jason = r#"
+        Base(String) :: { name: String, replicas: Int }
+        Base(name) { name: name, replicas: 1 }
+        Web(name, port) extends Base(name) { port: port }
+        out Web("site", 80)
+    "#;
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    assert_eq!(result, json!({ "name": "site", "replicas": 1, "port": 80 }));
+
+    let bad_override = r#"
+        Base(String) :: { name: String, replicas: Int }
+        Base(name) { name: name, replicas: 1 }
+        Web(name) extends Base(name) { replicas: "many" }
+        out Web("site")
+    "#;
+    let err = jason_src_to_json(bad_override).unwrap_err();
+    assert!(err.to_string().contains("replicas in template Web must be Int"), "{}", err);
+}
+
+#[test]
+fn test_template_extends_cycles() {
+    let self_extend = r#"A(x) extends A(x) { x: x } out A(1)"#;
+    assert!(jason_src_to_json(self_extend).is_err());
+
+    let cycle = r#"
+        A(x) extends B(x) { a: x }
+        B(x) extends A(x) { b: x }
+        out A(1)
+    "#;
+    let err = jason_src_to_json(cycle).unwrap_err();
+    assert!(err.to_string().contains("Template Recursion"), "{}", err);
+
+    let missing = r#"A(x) extends Missing(x) { a: x } out A(1)"#;
+    assert!(jason_src_to_json(missing).is_err());
+}
+
+#[test]
+fn test_templates_as_values() {
+    let jason = r#"
+        Person(name, age = 30) {
+            name: name,
+            age: age
+        }
+        Team(title, make, ...names) {
+            title: title,
+            members: names map make
+        }
+        maker = Person
+        out {
+            people: ["Alex", "Sam"] map Person,
+            stored: ["Jo"] map maker,
+            lead: maker("Jo", 41),
+            team: Team("core", Person, "Alex")
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "people": [{ "name": "Alex", "age": 30 }, { "name": "Sam", "age": 30 }],
+        "stored": [{ "name": "Jo", "age": 30 }],
+        "lead": { "name": "Jo", "age": 41 },
+        "team": { "title": "core", "members": [{ "name": "Alex", "age": 30 }] }
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_map_template_errors() {
+    let not_template = r#"out [1, 2] map 5"#;
+    let err = jason_src_to_json(not_template).unwrap_err();
+    assert!(err.to_string().contains("map without parameters applies a template"), "{}", err);
+
+    let arity = r#"
+        Pair(a, b) { a: a, b: b }
+        out [1, 2] map Pair
+    "#;
+    let err = jason_src_to_json(arity).unwrap_err();
+    assert!(err.to_string().contains("template Pair takes 2 arguments but 1 was given"), "{}", err);
+
+    // `map` on its own line is still a variable
+    let name = r#"
+        items = [1]
+        map = 3
+        out map
+    "#;
+    assert_eq!(jason_src_to_json(name).expect("failed to compile"), json!(3));
+
+    // templates can't be written out, at the top or nested
+    for src in ["P(x) { a: x }\nout P", "P(x) { a: x }\nout {p: [P]}"] {
+        let err = jason_src_to_json(src).unwrap_err();
+        assert!(err.to_string().contains("template P can't be part of the output"), "{}", err);
+    }
+
+    // an object built from data is never a template
+    let forged = r#"
+        P(x) { a: x }
+        m := from_entries([["$template", "P"]])
+        out ["x"] map m
+    "#;
+    let err = jason_src_to_json(forged).unwrap_err();
+    assert!(err.to_string().contains("map without parameters applies a template"), "{}", err);
+
+    // a template passed to itself recurses without naming itself
+    let err = jason_src_to_json("T(f) { a: [f] map f }\nout T(T)").unwrap_err();
+    assert!(err.to_string().contains("template T went past the call depth limit"), "{}", err);
+}
+
+#[test]
+fn test_template_values_are_not_objects() {
+    let cases = [
+        ("out Person + { a: 1 }", "can't be added with +"),
+        ("out { a: 1 } + Person", "can't be added with +"),
+        ("out Person & { a: 1 }", "can't be merged with &"),
+        ("out {p: Person + {a: 1}}", "can't be added with +"),
+        ("out keys(Person)", "can't be passed to keys"),
+        ("out entries(Person)", "can't be passed to entries"),
+        ("out values(Person)", "can't be passed to values"),
+        ("out len(Person)", "can't be passed to len"),
+        ("out str([Person])", "can't be turned into a string"),
+        ("out Person.name", "can't be accessed with a path"),
+        ("out Person at \"name\"", "can't be indexed with at"),
+        ("out type(Person)!", "can't be passed to lua"),
+    ];
+    for (src, message) in cases {
+        let src = format!("Person(name) {{ name: name }}\n{}", src);
+        let err = jason_src_to_json(&src).unwrap_err();
+        assert!(err.to_string().contains(&format!("template Person {}", message)), "{}: {}", src, err);
+        assert!(!err.to_string().contains("$template"), "{}: {}", src, err);
+    }
+
+    // type errors name the template instead of showing how it's stored
+    let typed = "Person(name) { name: name }\nf(Int) :: Int\nf(x) => x\nout f(Person)";
+    let err = jason_src_to_json(typed).unwrap_err();
+    assert!(err.to_string().contains("expected type Int for x found Template Person"), "{}", err);
+}
+
+#[test]
+fn test_functions() {
+    let jason = r#"
+        double(Int) :: Int
+        double(x) => x * 2
+        fact(n) => if n <= 1 then 1 else n * fact(n - 1)
+        label(name, port) => name + ":" + str(port)
+        scale(xs, k) => xs map(n) n * k
+        n = 100
+        out {
+            double: double(21),
+            fact: fact(5),
+            label: label("web", 80),
+            scaled: scale([1, 2], 3),
+            n: n
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "double": 42,
+        "fact": 120,
+        "label": "web:80",
+        "scaled": [3, 6],
+        "n": 100
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_function_errors() {
+    let typed = r#"
+        double(Int) :: Int
+        double(x) => x * 2
+        out double("a")
+    "#;
+    let err = jason_src_to_json(typed).unwrap_err();
+    assert!(err.to_string().contains("expected type Int for x found String in function double"), "{}", err);
+
+    let arity = r#"
+        id(x) => x
+        out id(1, 2)
+    "#;
+    let err = jason_src_to_json(arity).unwrap_err();
+    assert!(err.to_string().contains("function id takes 1 argument but 2 were given"), "{}", err);
+
+    let endless = r#"
+        loop(x) => loop(x)
+        out loop(1)
+    "#;
+    let err = jason_src_to_json(endless).unwrap_err();
+    assert!(err.to_string().contains("call depth limit"), "{}", err);
+
+    // a real body takes far more stack per call, it has to hit the limit before the test thread's stack runs out
+    let deep = r#"
+        count(x) => if x <= 0 then 0 else count(x - 1) + 1
+        out [count(10), count(100)]
+    "#;
+    let err = jason_src_to_json(deep).unwrap_err();
+    assert!(err.to_string().contains("function count went past the call depth limit of 12"), "{}", err);
+    assert_eq!(jason_src_to_json("count(x) => if x <= 0 then 0 else count(x - 1) + 1\nout count(11)").unwrap(), json!(11));
+
+    // the body can't see the caller's parameters
+    let scoped = r#"
+        peek() => secret
+        wrap(secret) => peek()
+        out wrap(1)
+    "#;
+    assert!(jason_src_to_json(scoped).is_err());
+
+    // functions and templates share their names
+    let clashes = [
+        ("T(x) { a: x }\nT(x) => x\nout T(1)", "function T has the same name as a template"),
+        ("T(x) => x\nT(x) { a: x }\nout T(1)", "template T has the same name as a function"),
+    ];
+    for (src, message) in clashes {
+        let err = jason_src_to_json(src).unwrap_err();
+        assert!(err.to_string().contains(message), "{}: {}", src, err);
+    }
+}
+
+#[test]
+fn test_template_scoping() {
+    let jason = r#"
+        name = "global"
+        Inner(name) { name: name }
+        Outer(name, other) {
+            inner: Inner(other),
+            name: name,
+            items: [1, 2] map(name) name * 10
+        }
+        out { outer: Outer("a", "b"), name: name }
+    "#;
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "outer": { "inner": { "name": "b" }, "name": "a", "items": [10, 20] },
+        "name": "global"
+    });
+    assert_eq!(result, expected);
+
+    // a template can't see the parameters of the template calling it
+    let caller = r#"
+        Peek() { seen: hidden }
+        Outer(hidden) { peek: Peek() }
+        out Outer(1)
+    "#;
+    let err = jason_src_to_json(caller).unwrap_err();
+    assert!(err.to_string().contains("the variable hidden does not exist"), "{}", err);
+
+    // a failing template doesn't leave its parameters behind
+    let failing = r#"
+        Fail(secret) { value: secret + missing }
+        bad = Fail(1)
+        out secret
+    "#;
+    let report = jason_src_check(failing);
+    assert_eq!(report.errors.len(), 2);
+    assert!(report.errors[1].to_string().contains("the variable secret does not exist"), "{}", report.errors[1]);
+}
+
+#[test]
+fn test_let_bindings() {
+    let jason = r#"
+        base = 1
+        Service(name) {
+            let full = name + "-svc",
+            name: full,
+            port: let p = 80 in p + 1
+        }
+        out {
+            ports: let base = 8080 in { http: base, https: base + 1 },
+            nested: let a = 1 in let b = a + 1 in [a, b],
+            service: Service("api"),
+            local: { let x = 2, y: x * 3 },
+            base: base
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "ports": { "http": 8080, "https": 8081 },
+        "nested": [1, 2],
+        "service": { "name": "api-svc", "port": 81 },
+        "local": { "y": 6 },
+        "base": 1
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_let_does_not_leak() {
+    let outside = r#"
+        a = { let hidden = 1, shown: hidden }
+        out hidden
+    "#;
+    let err = jason_src_to_json(outside).unwrap_err();
+    assert!(err.to_string().contains("the variable hidden does not exist"), "{}", err);
+
+    let top_level = r#"
+        let x = 1
+        out 2
+    "#;
+    let err = jason_src_to_json(top_level).unwrap_err();
+    assert!(err.to_string().contains("needs `in` outside of a block"), "{}", err);
+}
+
+#[test]
+fn test_let_and_in_as_keys() {
+    let jason = r#"
+        out { let: 1, in: 2, let x = 3, both: let y = 4 in x + y }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    assert_eq!(result, json!({ "let": 1, "in": 2, "both": 7 }));
+}
+
+#[test]
+fn test_filter_and_reduce() {
+    let jason = r#"
+        items = [
+            {name: "a", price: 3, active: true},
+            {name: "b", price: 5, active: false},
+            {name: "c", price: 7, active: true}
+        ]
+        out {
+            active: (items filter(item) item.active) map(item) item.name,
+            evens: [1, 2, 3, 4, 5] filter(n, i) i % 2 == 0,
+            total: items reduce(acc, item) acc + item.price from 0,
+            active_total: (items filter(i) i.active) reduce(sum, i) sum + i.price from 0,
+            indexed: ["x", "y"] reduce(acc, s, i) acc + s + str(i) from "",
+            empty: [] reduce(acc, n) acc + n from 10
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "active": ["a", "c"],
+        "evens": [1, 3, 5],
+        "total": 15,
+        "active_total": 10,
+        "indexed": "x0y1",
+        "empty": 10
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_filter_and_reduce_errors() {
+    let err = jason_src_to_json(r#"out [1, 2] filter(n) n"#).unwrap_err();
+    assert!(err.to_string().contains("filter condition must be of type Bool found Int"), "{}", err);
+
+    let err = jason_src_to_json(r#"out 5 filter(n) true"#).unwrap_err();
+    assert!(err.to_string().contains("left side of filter must be of type List found Int"), "{}", err);
+
+    let err = jason_src_to_json(r#"out [1] reduce(n) n from 0"#).unwrap_err();
+    assert!(err.to_string().contains("reduce takes 2 or 3 names but 1 was given"), "{}", err);
+
+    let err = jason_src_to_json(r#"out [1] reduce(acc, n) acc + n"#).unwrap_err();
+    assert!(err.to_string().contains("expected `from` in reduce expression"), "{}", err);
+}
+
+#[test]
+fn test_sort_unique_group_reverse() {
+    let jason = r#"
+        people = [
+            {name: "b", age: 30, team: "x"},
+            {name: "a", age: 25, team: "y"},
+            {name: "c", age: 30, team: "x"}
+        ]
+        out {
+            nums: [3, 1.5, 2, 1] sort,
+            strs: ["b", "a", "c"] sort reverse,
+            lists: [[2, 1], [1, 2], [1]] sort,
+            by_age: (people sort by(p) p.age) map(p) p.name,
+            uniq: [1, 2, 1.0, "a", "a", [1], [1]] unique,
+            teams: people group by(p) p.team,
+            ages: people group by(p) p.age
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let b = json!({ "name": "b", "age": 30, "team": "x" });
+    let a = json!({ "name": "a", "age": 25, "team": "y" });
+    let c = json!({ "name": "c", "age": 30, "team": "x" });
+    let expected = json!({
+        "nums": [1, 1.5, 2, 3],
+        "strs": ["c", "b", "a"],
+        "lists": [[1], [1, 2], [2, 1]],
+        "by_age": ["a", "b", "c"],
+        "uniq": [1, 2, "a", [1]],
+        "teams": { "x": [b, c], "y": [a] },
+        "ages": { "25": [a], "30": [b, c] }
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_sort_words_are_still_names() {
+    let keys = jason_src_to_json(r#"out {group: "admins", sort: 1, unique: true, reverse: false}"#).expect("failed to compile");
+    assert_eq!(keys, json!({ "group": "admins", "sort": 1, "unique": true, "reverse": false }));
+
+    let jason = r#"
+        sort = [2, 1]
+        group = "g"
+        out { sorted: sort sort, group: group }
+    "#;
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    assert_eq!(result, json!({ "sorted": [1, 2], "group": "g" }));
+}
+
+#[test]
+fn test_sort_and_group_errors() {
+    let err = jason_src_to_json(r#"out [1, "a"] sort"#).unwrap_err();
+    assert!(err.to_string().contains("sort can't order"), "{}", err);
+
+    let err = jason_src_to_json(r#"out [{a: 1}, {a: 2}] sort"#).unwrap_err();
+    assert!(err.to_string().contains("sort can't order"), "{}", err);
+
+    // only some pairs of these can be compared, it's still an error and never a panic
+    let err = jason_src_to_json(r#"out [[0], [1, "a"], [1, 2]] sort"#).unwrap_err();
+    assert!(err.to_string().contains("sort can't order [Int] and ["), "{}", err);
+
+    let err = jason_src_to_json(r#"out (range(0, 200) map(n) if n % 3 == 0 then str(n) else n) sort"#).unwrap_err();
+    assert!(err.to_string().contains("sort can't order"), "{}", err);
+
+    let err = jason_src_to_json(r#"out [[1]] group by(n) n"#).unwrap_err();
+    assert!(err.to_string().contains("group keys must be of type String, Number, Bool or Null"), "{}", err);
+
+    let err = jason_src_to_json(r#"out [1] group"#).unwrap_err();
+    assert!(err.to_string().contains("expected `by(item) key` after group"), "{}", err);
+}
+
+#[test]
+fn test_list_and_object_builtins() {
+    let jason = r#"
+        cfg = {host: "a", port: 80}
+        out {
+            len: [len([1, 2]), len("héllo"), len(cfg)],
+            keys: keys(cfg),
+            values: values(cfg),
+            entries: entries(cfg),
+            back: from_entries(entries(cfg)),
+            flat: flatten([[1, [2]], 3]),
+            deep: flatten([[1, [2]], 3], 5),
+            zip: zip([1, 2, 3], ["a", "b"]),
+            ranges: [range(3), range(1, 4), range(10, 0, -3)],
+            sums: [sum([1, 2, 3]), sum([1, 2.5]), sum([])],
+            extremes: [min([3, 1, 2]), max(["a", "c", "b"])],
+            avg: avg([1, 2]),
+            has: [contains([1, 2], 2.0), contains("hello", "ell"), contains(cfg, "port")],
+            index: [index_of([1, 2], 2), index_of("héllo", "l"), index_of([1], 5)]
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "len": [2, 5, 2],
+        "keys": ["host", "port"],
+        "values": ["a", 80],
+        "entries": [["host", "a"], ["port", 80]],
+        "back": { "host": "a", "port": 80 },
+        "flat": [1, [2], 3],
+        "deep": [1, 2, 3],
+        "zip": [[1, "a"], [2, "b"]],
+        "ranges": [[0, 1, 2], [1, 2, 3], [10, 7, 4, 1]],
+        "sums": [6, 3.5, 0],
+        "extremes": [1, "c"],
+        "avg": 1.5,
+        "has": [true, true, true],
+        "index": [1, 2, -1]
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_builtins_can_be_redefined() {
+    let jason = r#"
+        values(a) { v: a }
+        len(Int) :: Int
+        len(x) => x * 2
+        out { template: values(1), function: len(4), builtin: sum([1, 2]) }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    assert_eq!(result, json!({ "template": { "v": 1 }, "function": 8, "builtin": 3 }));
+}
+
+#[test]
+fn test_builtin_errors() {
+    let cases = [
+        ("out len(1)", "len expects List, String or Object for its 1st argument found Int"),
+        ("out len()", "len takes 1 argument but 0 were given"),
+        ("out range(1, 2, 3, 4)", "range takes 1 to 3 arguments but 4 were given"),
+        ("out zip([1])", "zip takes 2 or more arguments but 1 was given"),
+        ("out sum([1, \"a\"])", "sum expects [Number] for its 1st argument"),
+        ("out min([])", "min of an empty list"),
+        ("out max([1, \"a\"])", "max can't compare"),
+        ("out range(0, 5, 0)", "range step can't be 0"),
+        ("out from_entries([[1, 2]])", "from_entries expects [key, value] pairs"),
+    ];
+    for (src, message) in cases {
+        let err = jason_src_to_json(src).unwrap_err();
+        assert!(err.to_string().contains(message), "{}: {}", src, err);
+    }
+}
+
+#[test]
+fn test_string_builtins() {
+    let jason = r#"
+        email = "jasondev@gmail.com"
+        out {
+            user: split(email, "@")[0],
+            upper: upper("héllo"),
+            lower: lower("ABC"),
+            chars: split("héy", ""),
+            joined: join(["a", "b", "c"], "-"),
+            replaced: replace("a.b.c", ".", "/"),
+            trimmed: trim("  x  "),
+            affixes: [starts_with(email, "jason"), ends_with(email, ".org")],
+            padded: [pad_left("7", 3, "0"), pad_left("héllo", 3), pad_left("ab", 4)],
+            subs: [substr("héllo", 1, 3), substr("héllo", -2), substr("abc", 5)]
+        }
+    "#;
+
+    let result = jason_src_to_json(jason).expect("failed to compile");
+    let expected = json!({
+        "user": "jasondev",
+        "upper": "HÉLLO",
+        "lower": "abc",
+        "chars": ["h", "é", "y"],
+        "joined": "a-b-c",
+        "replaced": "a/b/c",
+        "trimmed": "x",
+        "affixes": [true, false],
+        "padded": ["007", "héllo", "  ab"],
+        "subs": ["éll", "lo", ""]
+    });
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_string_builtin_errors() {
+    let cases = [
+        ("out upper(1)", "upper expects String for its 1st argument found Int"),
+        ("out join([1], \",\")", "join expects [String] for its 1st argument"),
+        ("out pad_left(\"a\", 3, \"ab\")", "pad_left pads with a single character"),
+        ("out substr(\"abc\", 0, -1)", "substr length can't be negative"),
+        ("out replace(\"abc\", \"b\")", "replace takes 3 arguments but 2 were given"),
+    ];
+    for (src, message) in cases {
+        let err = jason_src_to_json(src).unwrap_err();
+        assert!(err.to_string().contains(message), "{}: {}", src, err);
+    }
+}
+
+#[test]
+fn test_regex_builtins() {
+    let src = r#"
+        hosts = ["api-1.prod", "web.staging", "Bad_Name"]
+        out {
+            valid: hosts filter(h) matches(h, "^[a-z0-9-]+\\.[a-z]+$"),
+            parts: captures("api-1.prod", "^([a-z]+)(-\\d+)?\\.(\\w+)$"),
+            optional: captures("web.prod", "^([a-z]+)(-\\d+)?\\.(\\w+)$"),
+            missing: captures("web", "\\d"),
+            fixed: regex_replace("Bad_Name", "[^a-z]+", "-"),
+            swapped: regex_replace("a=1, b=2", "(\\w)=(\\d)", "$2=$1"),
+            named: regex_replace("v1.2", "v(?P<major>\\d+)\\.\\d+", "${major}.x")
+        }
+    "#;
+
+    let result = jason_src_to_json(src).unwrap();
+    assert_eq!(result, json!({
+        "valid": ["api-1.prod", "web.staging"],
+        "parts": ["api-1.prod", "api", "-1", "prod"],
+        "optional": ["web.prod", "web", null, "prod"],
+        "missing": null,
+        "fixed": "-ad-ame",
+        "swapped": "1=a, 2=b",
+        "named": "1.x"
+    }));
+}
+
+#[test]
+fn test_regex_builtin_errors() {
+    let cases = [
+        ("out matches(\"a\", \"(\")", "Value Error"),
+        ("out regex_replace(\"a\", \"[a\", \"b\")", "invalid regular expression \"[a\""),
+        ("out captures(\"a\", 1)", "captures expects String for its 2nd argument found Int"),
+        ("out matches(\"a\")", "matches takes 2 arguments but 1 was given"),
+    ];
+    for (src, message) in cases {
+        let err = jason_src_to_json(src).unwrap_err();
+        assert!(err.to_string().contains(message), "{}: {}", src, err);
+    }
+}